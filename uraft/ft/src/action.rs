@@ -0,0 +1,70 @@
+//! Typed actions encoded in the `msg` of `ft_transfer_call`.
+//!
+//! A `msg` such as `"Deposit"` or `{"Stake":{"pool":"pool.near"}}` is parsed into a
+//! `TransferAction` and forwarded to the receiver in its canonical JSON form. Any other `msg`
+//! is forwarded verbatim, as the standard requires.
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{serde_json, AccountId};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TransferAction {
+    Deposit,
+    Stake { pool: AccountId },
+    Swap { min_out: U128 },
+}
+
+impl TransferAction {
+    /// Parses `msg` into an action, or returns `None` if it isn't one.
+    pub fn parse(msg: &str) -> Option<Self> {
+        serde_json::from_str(msg).ok()
+    }
+}
+
+/// Returns the `msg` to forward to the receiver of `ft_transfer_call`.
+pub(crate) fn route_transfer_msg(msg: String) -> String {
+    match TransferAction::parse(&msg) {
+        Some(action) => serde_json::to_string(&action).unwrap(),
+        None => msg,
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_deposit() {
+        assert_eq!(TransferAction::parse("\"Deposit\""), Some(TransferAction::Deposit));
+    }
+
+    #[test]
+    fn test_parse_stake() {
+        assert_eq!(
+            TransferAction::parse(r#"{"Stake": {"pool": "alice"}}"#),
+            Some(TransferAction::Stake { pool: accounts(0) })
+        );
+    }
+
+    #[test]
+    fn test_parse_swap() {
+        assert_eq!(
+            TransferAction::parse(r#"{"Swap":{"min_out":"100"}}"#),
+            Some(TransferAction::Swap { min_out: U128(100) })
+        );
+        assert_eq!(
+            route_transfer_msg(r#"{"Swap": {"min_out": "100"}}"#.to_string()),
+            r#"{"Swap":{"min_out":"100"}}"#
+        );
+    }
+
+    #[test]
+    fn test_malformed_msg_is_forwarded_verbatim() {
+        assert_eq!(TransferAction::parse("{\"Stake\": {}}"), None);
+        assert_eq!(route_transfer_msg("take-my-money".to_string()), "take-my-money");
+        assert_eq!(route_transfer_msg(String::new()), "");
+    }
+}
@@ -0,0 +1,129 @@
+//! ERC20-style allowances: an account approves a spender, which can then transfer tokens on the
+//! account's behalf up to the approved amount.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Allows `spender_id` to transfer up to `amount` tokens from the predecessor's balance,
+    /// replacing any previous allowance. An `amount` of zero revokes the allowance.
+    /// The attached deposit has to cover the storage of a new allowance, the rest is refunded.
+    #[payable]
+    pub fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        assert!(env::attached_deposit() > 0, "Requires attached deposit of at least 1 yoctoNEAR");
+        let owner_id = env::predecessor_account_id();
+        assert!(owner_id != spender_id, "Owner and spender should be different");
+        let initial_storage_usage = env::storage_usage();
+        let key = (owner_id, spender_id);
+        if amount.0 > 0 {
+            self.allowances.insert(&key, &amount.0);
+        } else {
+            self.allowances.remove(&key);
+        }
+        log!("@{} approved @{} to spend {}", key.0, key.1, amount.0);
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    /// Returns how many tokens `spender_id` can still transfer from `owner_id`.
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        self.allowances.get(&(owner_id, spender_id)).unwrap_or(0).into()
+    }
+
+    /// Transfers `amount` tokens from `owner_id` to `receiver_id` on behalf of the predecessor,
+    /// spending the allowance given by `owner_id`.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let key = (owner_id, env::predecessor_account_id());
+        let allowance = self.allowances.get(&key).unwrap_or(0);
+        assert!(allowance >= amount.0, "The allowance is not enough");
+        if let Some(rejection) = self.trip_circuit_breaker(&key.0, &receiver_id, amount.into()) {
+            rejection.as_return();
+            return;
+        }
+        let remaining = allowance - amount.0;
+        if remaining > 0 {
+            self.allowances.insert(&key, &remaining);
+        } else {
+            self.allowances.remove(&key);
+        }
+        self.internal_transfer(&key.0, &receiver_id, amount.into(), memo);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_approve(accounts(3), 1_000.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(3))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_from_partial_allowance() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(3)).0, 1_000);
+        contract.ft_transfer_from(accounts(2), accounts(1), 400.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(3)).0, 600);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 400);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 400);
+    }
+
+    #[test]
+    fn test_transfer_from_whole_allowance() {
+        let (_, mut contract) = setup();
+        contract.ft_transfer_from(accounts(2), accounts(1), 1_000.into(), None);
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(3)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The allowance is not enough")]
+    fn test_transfer_from_over_allowance() {
+        let (_, mut contract) = setup();
+        contract.ft_transfer_from(accounts(2), accounts(1), 600.into(), None);
+        contract.ft_transfer_from(accounts(2), accounts(1), 600.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach")]
+    fn test_approve_without_storage_deposit() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ft_approve(accounts(4), 1_000.into());
+    }
+}
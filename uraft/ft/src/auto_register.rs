@@ -0,0 +1,129 @@
+//! Transfers to unregistered accounts. While auto-registration is allowed, the sender of
+//! `ft_transfer_register` pays the registration of the receiver, so tokens can be sent to accounts
+//! that never called `storage_deposit`.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Allows or forbids `ft_transfer_register`. Can only be called by the owner.
+    pub fn set_allow_auto_register(&mut self, allowed: bool) {
+        self.assert_owner();
+        self.allow_auto_register = allowed;
+        log!("Auto-registration set to {} by @{}", allowed, self.owner_id);
+    }
+
+    /// Returns whether `ft_transfer_register` is allowed.
+    pub fn is_auto_register_allowed(&self) -> bool {
+        self.allow_auto_register
+    }
+
+    /// Same as `ft_transfer`, but registers `receiver_id` first if it isn't registered yet. The
+    /// attached deposit then has to cover the minimum storage balance and the registration
+    /// premium, like `storage_deposit`, and the rest of it is refunded. If the receiver is already
+    /// registered, 1 yoctoNEAR is kept like the deposit of `ft_transfer` and the rest is refunded.
+    /// Not available while a fee token is set.
+    #[payable]
+    pub fn ft_transfer_register(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert!(self.allow_auto_register, "Auto-registration is disabled");
+        assert!(
+            self.fee_token_id.is_none(),
+            "Transfers with registration aren't available while transfers pay a fee in another token"
+        );
+        let mut deposit = env::attached_deposit();
+        require(deposit > 0, ContractError::OneYocto);
+        let sender_id = env::predecessor_account_id();
+        if let Some(rejection) = self.trip_circuit_breaker(&sender_id, &receiver_id, amount.into())
+        {
+            Promise::new(sender_id).transfer(deposit);
+            rejection.as_return();
+            return;
+        }
+        if !self.token.accounts.contains_key(&receiver_id) {
+            deposit = self.internal_register_with_deposit(&receiver_id, deposit);
+        } else {
+            deposit -= 1;
+        }
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+        if deposit > 0 {
+            Promise::new(sender_id).transfer(deposit);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.set_allow_auto_register(true);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_registers_receiver() {
+        let (mut context, mut contract) = setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance + 10)
+            .build());
+        contract.ft_transfer_register(accounts(1), 1_000.into(), None);
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert!(contract.storage_balance_of(accounts(1)).is_some());
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(2));
+        assert!(format!("{:?}", receipts[0].actions).contains("deposit: 10"));
+    }
+
+    #[test]
+    fn test_transfer_register_to_registered_receiver() {
+        let (mut context, mut contract) = setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance)
+            .build());
+        contract.ft_transfer_register(accounts(1), 1_000.into(), None);
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(10).build());
+        contract.ft_transfer_register(accounts(1), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 2_000);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert!(format!("{:?}", receipts[0].actions).contains("deposit: 9"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_STORAGE")]
+    fn test_transfer_register_without_enough_deposit() {
+        let (mut context, mut contract) = setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context.attached_deposit(min_balance - 1).build());
+        contract.ft_transfer_register(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Auto-registration is disabled")]
+    fn test_transfer_register_disabled() {
+        let (mut context, mut contract) = setup();
+        contract.set_allow_auto_register(false);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_register(accounts(1), 1_000.into(), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! Gas benchmarks of the hot paths, for catching performance regressions in simulation tests.
+//! Only compiled with the `benchmarks` feature, so they're never part of a production build.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Runs `n` transfers of 1 token back and forth between the owner and the treasury, through
+    /// the same checks as `ft_transfer`, and returns the gas they used. The owner needs at least
+    /// 1 token. Can only be called by the owner.
+    pub fn bench_transfer(&mut self, n: u32) -> U64 {
+        self.assert_owner();
+        let owner_id = self.owner_id.clone();
+        let treasury_id = self.treasury_id.clone();
+        assert!(owner_id != treasury_id, "The treasury has to be different from the owner");
+        let initial_gas = env::used_gas();
+        for i in 0..n {
+            if i % 2 == 0 {
+                self.internal_transfer(&owner_id, &treasury_id, 1, None);
+            } else {
+                self.internal_transfer(&treasury_id, &owner_id, 1, None);
+            }
+        }
+        let used_gas = env::used_gas().0 - initial_gas.0;
+        log!("{} transfers used {} gas", n, used_gas);
+        used_gas.into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_bench_transfer() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(2),
+            1_000.into(),
+            FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: "Benchmark token".to_string(),
+                symbol: "BENCH".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 6,
+            },
+            Some(InitConfig { treasury_id: Some(accounts(3)), ..Default::default() }),
+        );
+        let used_gas = contract.bench_transfer(3);
+        assert!(used_gas.0 > 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 1);
+        assert!(get_logs().last().unwrap().starts_with("3 transfers used"));
+    }
+}
@@ -0,0 +1,99 @@
+//! Where burned tokens go. By default a burn reduces the total supply, but some ecosystems
+//! prefer sending burned tokens to a dead account, which keeps the total supply stable. The burn
+//! mode is chosen at initialization and applies to `ft_burn` and to the burned part of
+//! transfers.
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BurnMode {
+    /// Burned tokens are destroyed and the total supply is reduced.
+    #[default]
+    ReduceSupply,
+    /// Burned tokens are transferred to the given account, which nobody controls.
+    SendToDead(AccountId),
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns where burned tokens go.
+    pub fn get_burn_mode(&self) -> BurnMode {
+        self.burn_mode.clone()
+    }
+}
+
+impl Contract {
+    /// Returns the dead account burned tokens are sent to, or `None` if burns reduce the supply.
+    pub(crate) fn dead_account(&self) -> Option<AccountId> {
+        match &self.burn_mode {
+            BurnMode::ReduceSupply => None,
+            BurnMode::SendToDead(dead_id) => Some(dead_id.clone()),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup(burn_mode: BurnMode) -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: "Burn token".to_string(),
+                symbol: "BURN".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 6,
+            },
+            Some(InitConfig { burn_basis_points: 1_000, burn_mode, ..Default::default() }),
+        );
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_burn_reduces_supply() {
+        let (_, mut contract) = setup(BurnMode::ReduceSupply);
+        contract.ft_burn(1_000.into(), None);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 2_000);
+    }
+
+    #[test]
+    fn test_burn_sends_to_dead_account() {
+        let (_, mut contract) = setup(BurnMode::SendToDead(accounts(4)));
+        assert_eq!(contract.get_burn_mode(), BurnMode::SendToDead(accounts(4)));
+        contract.ft_burn(1_000.into(), None);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 2_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        contract.debug_assert_supply_consistent();
+    }
+}
@@ -0,0 +1,176 @@
+//! Records of unregistered accounts, kept as an audit trail until the owner purges them. The
+//! storage of a record is kept from the refund of `storage_unregister`, so closing accounts costs
+//! the contract nothing.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClosedRecord {
+    /// The balance the account had when it was closed, which was burned.
+    pub final_balance: U128,
+    /// The block timestamp at which the account was closed.
+    pub closed_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the record of the latest closure of `account_id`, or `None` if it was never closed
+    /// or its record was purged.
+    pub fn closed_account_info(&self, account_id: AccountId) -> Option<ClosedRecord> {
+        self.closed_accounts.get(&account_id)
+    }
+
+    /// Checks up to `limit` records starting at `from_index` and removes the ones of accounts
+    /// closed before `before_ts` to reclaim their storage. A removed record is replaced by the
+    /// last one, which is checked next, so the following call has to continue at the returned
+    /// index. Returns `None` once every record is checked. Can only be called by the owner.
+    pub fn purge_closed_records(
+        &mut self,
+        before_ts: U64,
+        from_index: u64,
+        limit: u64,
+    ) -> Option<u64> {
+        self.assert_owner();
+        let mut index = from_index;
+        let mut purged = 0;
+        for _ in 0..limit {
+            let account_id = match self.closed_accounts.keys_as_vector().get(index) {
+                Some(account_id) => account_id,
+                None => break,
+            };
+            if self.closed_accounts.get(&account_id).unwrap().closed_at.0 < before_ts.0 {
+                self.closed_accounts.remove(&account_id);
+                purged += 1;
+            } else {
+                index += 1;
+            }
+        }
+        log!("Purged {} closed account records", purged);
+        if index < self.closed_accounts.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+}
+
+impl Contract {
+    /// Records the closure of `account_id` and returns the storage the record takes, which isn't
+    /// refunded to the account.
+    pub(crate) fn internal_record_closed_account(
+        &mut self,
+        account_id: &AccountId,
+        balance: Balance,
+    ) -> StorageUsage {
+        let initial_storage_usage = env::storage_usage();
+        let record = ClosedRecord {
+            final_balance: balance.into(),
+            closed_at: env::block_timestamp().into(),
+        };
+        self.closed_accounts.insert(account_id, &record);
+        env::storage_usage().saturating_sub(initial_storage_usage)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), 1_000_000.into(), None, None);
+        for account_id in [accounts(1), accounts(3)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .predecessor_account_id(account_id)
+                .build());
+            contract.storage_deposit(None, None);
+        }
+        (context, contract)
+    }
+
+    fn close_account(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        account_id: AccountId,
+        ts: u64,
+    ) {
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(account_id)
+            .block_timestamp(ts)
+            .build());
+        assert!(contract.storage_unregister(Some(true)));
+    }
+
+    #[test]
+    fn test_closed_account_is_recorded() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        close_account(&mut context, &mut contract, accounts(1), 100);
+
+        assert_eq!(
+            contract.closed_account_info(accounts(1)),
+            Some(ClosedRecord { final_balance: 1_000.into(), closed_at: 100.into() })
+        );
+        assert_eq!(contract.closed_account_info(accounts(3)), None);
+    }
+
+    #[test]
+    fn test_purge_closed_records() {
+        let (mut context, mut contract) = setup();
+        close_account(&mut context, &mut contract, accounts(1), 100);
+        close_account(&mut context, &mut contract, accounts(3), 200);
+
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.purge_closed_records(150.into(), 0, 10), None);
+        assert_eq!(contract.closed_account_info(accounts(1)), None);
+        assert!(contract.closed_account_info(accounts(3)).is_some());
+    }
+
+    #[test]
+    fn test_purge_closed_records_in_pages() {
+        let (mut context, mut contract) = setup();
+        close_account(&mut context, &mut contract, accounts(1), 100);
+        close_account(&mut context, &mut contract, accounts(3), 200);
+
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(2)).build());
+        // The first record is removed and replaced by the second one, which is kept.
+        assert_eq!(contract.purge_closed_records(300.into(), 0, 1), Some(0));
+        assert_eq!(contract.closed_account_info(accounts(1)), None);
+        assert!(contract.closed_account_info(accounts(3)).is_some());
+        assert_eq!(contract.purge_closed_records(300.into(), 0, 1), None);
+        assert_eq!(contract.closed_account_info(accounts(3)), None);
+    }
+
+    #[test]
+    fn test_closed_record_storage_is_not_refunded() {
+        let (mut context, mut contract) = setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        let initial_storage_usage = env::storage_usage();
+        testing_env!(context
+            .storage_usage(initial_storage_usage)
+            .attached_deposit(min_balance)
+            .predecessor_account_id(accounts(4))
+            .build());
+        contract.storage_deposit(None, None);
+        close_account(&mut context, &mut contract, accounts(4), 100);
+
+        // Only the record is left, and the part of the deposit that isn't refunded pays for it.
+        let record_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        assert!(record_cost > 0);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert!(format!("{:?}", receipts[0].actions)
+            .contains(&format!("deposit: {}", min_balance - record_cost + 1)));
+    }
+}
@@ -0,0 +1,103 @@
+//! The optional settings of `new`, and a single view of the settings of the contract, e.g. for an
+//! admin panel.
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// The optional settings of `new`. Every field can be left out of the JSON.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde", default)]
+pub struct InitConfig {
+    /// Minting can never increase the total supply above it, and the initial supply can't exceed
+    /// it.
+    pub max_supply: Option<U128>,
+    /// Receives the transfer fees, defaults to the owner.
+    pub treasury_id: Option<AccountId>,
+    /// The share of every transfer that goes to the treasury.
+    pub fee_basis_points: u16,
+    /// The share of every transfer that is burned.
+    pub burn_basis_points: u16,
+    /// If set, the decimals of the metadata have to match it.
+    pub expected_decimals: Option<u8>,
+    /// The tokens are only created by depositing NEAR with `near_deposit`, so the initial supply
+    /// has to be 0.
+    pub wrappable: bool,
+    /// No tokens can be minted after this block timestamp.
+    pub mint_deadline_ns: Option<U64>,
+    /// Whether burned tokens reduce the total supply, the default, or are sent to a dead account.
+    pub burn_mode: BurnMode,
+    /// The initial supply vests to the owner linearly over this duration and has to be released
+    /// with `claim_unlocked`.
+    pub owner_lock_duration_ns: Option<U64>,
+    /// Allows an initial supply of 0, which is usually a mistake unless the token is `wrappable`.
+    pub allow_zero_supply: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractConfig {
+    pub owner: AccountId,
+    /// Whether token transfers are paused.
+    pub paused: bool,
+    pub fee_basis_points: u16,
+    pub burn_basis_points: u16,
+    pub treasury_id: AccountId,
+    pub max_supply: Option<U128>,
+    pub total_supply: U128,
+    pub holders_count: u64,
+    pub min_transfer_amount: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the main settings of the contract in one call.
+    pub fn contract_config(&self) -> ContractConfig {
+        ContractConfig {
+            owner: self.get_owner(),
+            paused: self.is_paused(),
+            fee_basis_points: self.get_fee_basis_points(),
+            burn_basis_points: self.get_burn_basis_points(),
+            treasury_id: self.get_treasury(),
+            max_supply: self.get_max_supply(),
+            total_supply: self.ft_total_supply(),
+            holders_count: self.ft_holders_count(),
+            min_transfer_amount: self.get_min_transfer_amount(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_contract_config() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(2), 1_000_000.into(), Some(2_000_000.into()), None);
+        contract.set_min_transfer_amount(10.into());
+        let index = contract.set_fee_basis_points(50);
+        testing_env!(context
+            .block_timestamp(env::block_timestamp() + crate::timelock::MIN_ACTION_DELAY_NS)
+            .build());
+        contract.execute_action(index);
+        contract.pause();
+
+        let config = contract.contract_config();
+        assert_eq!(config.owner, contract.get_owner());
+        assert_eq!(config.paused, contract.is_paused());
+        assert!(config.paused);
+        assert_eq!(config.fee_basis_points, contract.get_fee_basis_points());
+        assert_eq!(config.fee_basis_points, 50);
+        assert_eq!(config.burn_basis_points, contract.get_burn_basis_points());
+        assert_eq!(config.treasury_id, contract.get_treasury());
+        assert_eq!(config.max_supply, contract.get_max_supply());
+        assert_eq!(config.total_supply, contract.ft_total_supply());
+        assert_eq!(config.holders_count, contract.ft_holders_count());
+        assert_eq!(config.min_transfer_amount, contract.get_min_transfer_amount());
+    }
+}
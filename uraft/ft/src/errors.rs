@@ -0,0 +1,240 @@
+//! Errors of the transfer, mint and storage paths. Each of them panics with a message of the
+//! form `ERR_CODE: description`. The codes are stable and meant to be matched by integrations,
+//! the descriptions are meant for humans and may change.
+//!
+//! | Code                            | Failure                                                  |
+//! |---------------------------------|----------------------------------------------------------|
+//! | `ERR_PAUSED`                    | The operation is paused                                  |
+//! | `ERR_SNAPSHOT_IN_PROGRESS`      | Balances are frozen while a snapshot is recorded         |
+//! | `ERR_AUTO_PAUSED`               | The transfer tripped the circuit breaker                 |
+//! | `ERR_BLACKLISTED`               | The sender or the receiver is blacklisted                |
+//! | `ERR_FROZEN`                    | The sender is frozen                                     |
+//! | `ERR_NOT_WHITELISTED`           | The sender or the receiver isn't whitelisted             |
+//! | `ERR_AMOUNT_BELOW_MINIMUM`      | The amount is below the minimum transfer amount          |
+//! | `ERR_MEMO_TOO_LONG`             | The memo exceeds the maximum memo length                 |
+//! | `ERR_MEMO_REQUIRED`             | The memo is missing while memos are required             |
+//! | `ERR_INSUFFICIENT_BALANCE`      | The sender doesn't have enough balance                   |
+//! | `ERR_LOCKED_BALANCE`            | The amount would spend locked or pending tokens          |
+//! | `ERR_DAILY_LIMIT_EXCEEDED`      | The sender has reached its daily transfer limit          |
+//! | `ERR_TRANSFER_COOLDOWN`         | The sender transferred too recently                      |
+//! | `ERR_SAME_ACCOUNT`              | The sender and the receiver are the same account         |
+//! | `ERR_ZERO_AMOUNT`               | The amount is zero                                       |
+//! | `ERR_NOT_REGISTERED`            | The receiver isn't registered                            |
+//! | `ERR_CALL_IN_PROGRESS`          | Another flow waits for its callback                      |
+//! | `ERR_ONE_YOCTO`                 | The call requires exactly 1 yoctoNEAR attached           |
+//! | `ERR_NOT_MINTER`                | The predecessor is neither the owner nor a minter        |
+//! | `ERR_MINTING_DISABLED`          | Wrapped NEAR can't be minted or burned directly          |
+//! | `ERR_MAX_SUPPLY_EXCEEDED`       | Minting would exceed the max supply                      |
+//! | `ERR_MINT_CAP_EXCEEDED`         | Minting would exceed the cap of the current epoch        |
+//! | `ERR_MINT_DEADLINE_PASSED`      | The mint deadline has passed                             |
+//! | `ERR_EMPTY_BATCH`               | A batch has no recipients                                |
+//! | `ERR_BATCH_LENGTH_MISMATCH`     | A batch has a different number of recipients and amounts |
+//! | `ERR_BATCH_TOO_LONG`            | A batch has too many recipients                          |
+//! | `ERR_AMOUNT_OVERFLOW`           | The amounts of a batch overflow                          |
+//! | `ERR_INSUFFICIENT_STORAGE`      | The attached deposit doesn't cover the storage cost      |
+use std::fmt;
+
+use near_sdk::{env, AccountId, Balance};
+
+#[derive(Debug, PartialEq)]
+pub enum ContractError {
+    Paused,
+    MintsPaused,
+    BurnsPaused,
+    SnapshotInProgress,
+    AutoPaused(Balance),
+    SenderBlacklisted,
+    ReceiverBlacklisted,
+    SenderFrozen,
+    SenderNotWhitelisted,
+    ReceiverNotWhitelisted,
+    AmountBelowMinimum,
+    MemoTooLong(u32),
+    MemoRequired,
+    InsufficientBalance,
+    LockedBalance,
+    DailyLimitExceeded,
+    TransferCooldown(u64),
+    SameAccount,
+    ZeroAmount,
+    NotRegistered(AccountId),
+    CallInProgress,
+    OneYocto,
+    NotMinter,
+    MintingDisabled,
+    MaxSupplyExceeded,
+    MintCapExceeded,
+    MintDeadlinePassed,
+    EmptyBatch,
+    BatchLengthMismatch,
+    BatchTooLong(u32),
+    AmountOverflow,
+    DepositBelowMinimum,
+    InsufficientStorage(Balance),
+    PremiumNotCovered(Balance),
+}
+
+impl ContractError {
+    /// Returns the stable code of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContractError::Paused | ContractError::MintsPaused | ContractError::BurnsPaused => {
+                "ERR_PAUSED"
+            }
+            ContractError::SnapshotInProgress => "ERR_SNAPSHOT_IN_PROGRESS",
+            ContractError::AutoPaused(_) => "ERR_AUTO_PAUSED",
+            ContractError::SenderBlacklisted | ContractError::ReceiverBlacklisted => {
+                "ERR_BLACKLISTED"
+            }
+            ContractError::SenderFrozen => "ERR_FROZEN",
+            ContractError::SenderNotWhitelisted | ContractError::ReceiverNotWhitelisted => {
+                "ERR_NOT_WHITELISTED"
+            }
+            ContractError::AmountBelowMinimum => "ERR_AMOUNT_BELOW_MINIMUM",
+            ContractError::MemoTooLong(_) => "ERR_MEMO_TOO_LONG",
+            ContractError::MemoRequired => "ERR_MEMO_REQUIRED",
+            ContractError::InsufficientBalance => "ERR_INSUFFICIENT_BALANCE",
+            ContractError::LockedBalance => "ERR_LOCKED_BALANCE",
+            ContractError::DailyLimitExceeded => "ERR_DAILY_LIMIT_EXCEEDED",
+            ContractError::TransferCooldown(_) => "ERR_TRANSFER_COOLDOWN",
+            ContractError::SameAccount => "ERR_SAME_ACCOUNT",
+            ContractError::ZeroAmount => "ERR_ZERO_AMOUNT",
+            ContractError::NotRegistered(_) => "ERR_NOT_REGISTERED",
+            ContractError::CallInProgress => "ERR_CALL_IN_PROGRESS",
+            ContractError::OneYocto => "ERR_ONE_YOCTO",
+            ContractError::NotMinter => "ERR_NOT_MINTER",
+            ContractError::MintingDisabled => "ERR_MINTING_DISABLED",
+            ContractError::MaxSupplyExceeded => "ERR_MAX_SUPPLY_EXCEEDED",
+            ContractError::MintCapExceeded => "ERR_MINT_CAP_EXCEEDED",
+            ContractError::MintDeadlinePassed => "ERR_MINT_DEADLINE_PASSED",
+            ContractError::EmptyBatch => "ERR_EMPTY_BATCH",
+            ContractError::BatchLengthMismatch => "ERR_BATCH_LENGTH_MISMATCH",
+            ContractError::BatchTooLong(_) => "ERR_BATCH_TOO_LONG",
+            ContractError::AmountOverflow => "ERR_AMOUNT_OVERFLOW",
+            ContractError::DepositBelowMinimum
+            | ContractError::InsufficientStorage(_)
+            | ContractError::PremiumNotCovered(_) => "ERR_INSUFFICIENT_STORAGE",
+        }
+    }
+
+    /// Aborts the call with the code and the description of the error.
+    pub fn panic(&self) -> ! {
+        env::panic_str(&self.to_string())
+    }
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.code())?;
+        match self {
+            ContractError::Paused => write!(f, "Contract is paused"),
+            ContractError::MintsPaused => write!(f, "Minting is paused"),
+            ContractError::BurnsPaused => write!(f, "Burning is paused"),
+            ContractError::SnapshotInProgress => {
+                write!(f, "Balances are frozen while a snapshot is recorded")
+            }
+            ContractError::AutoPaused(threshold) => write!(
+                f,
+                "The transfer exceeds the auto-pause threshold of {}, the contract is paused",
+                threshold
+            ),
+            ContractError::SenderBlacklisted => write!(f, "Sender is blacklisted"),
+            ContractError::ReceiverBlacklisted => write!(f, "Receiver is blacklisted"),
+            ContractError::SenderFrozen => write!(f, "Sender is frozen"),
+            ContractError::SenderNotWhitelisted => write!(f, "Sender is not whitelisted"),
+            ContractError::ReceiverNotWhitelisted => write!(f, "Receiver is not whitelisted"),
+            ContractError::AmountBelowMinimum => write!(f, "Amount below minimum"),
+            ContractError::MemoTooLong(max_len) => {
+                write!(f, "The memo can't be longer than {} bytes", max_len)
+            }
+            ContractError::MemoRequired => write!(f, "Transfers require a memo"),
+            ContractError::InsufficientBalance => {
+                write!(f, "The account doesn't have enough balance")
+            }
+            ContractError::LockedBalance => write!(f, "The amount exceeds the unlocked balance"),
+            ContractError::DailyLimitExceeded => write!(f, "The transfer exceeds the daily limit"),
+            ContractError::TransferCooldown(remaining_ns) => {
+                write!(f, "The next transfer is allowed in {} ns", remaining_ns)
+            }
+            ContractError::SameAccount => write!(f, "Sender and receiver should be different"),
+            ContractError::ZeroAmount => write!(f, "The amount should be a positive number"),
+            ContractError::NotRegistered(account_id) => {
+                write!(f, "The account {} is not registered", account_id)
+            }
+            ContractError::CallInProgress => {
+                write!(f, "Another cross-contract call is in progress")
+            }
+            ContractError::OneYocto => {
+                write!(f, "Requires attached deposit of exactly 1 yoctoNEAR")
+            }
+            ContractError::NotMinter => {
+                write!(f, "Only the owner or a minter can call this method")
+            }
+            ContractError::MintingDisabled => {
+                write!(f, "Wrapped NEAR can only be minted and burned by wrapping")
+            }
+            ContractError::MaxSupplyExceeded => write!(f, "Minting would exceed the max supply"),
+            ContractError::MintCapExceeded => {
+                write!(f, "Minting would exceed the cap of the current epoch")
+            }
+            ContractError::MintDeadlinePassed => write!(f, "The mint deadline has passed"),
+            ContractError::EmptyBatch => write!(f, "The list of recipients is empty"),
+            ContractError::BatchLengthMismatch => {
+                write!(f, "The number of recipients and amounts should be equal")
+            }
+            ContractError::BatchTooLong(max_len) => {
+                write!(f, "A batch can have at most {} recipients", max_len)
+            }
+            ContractError::AmountOverflow => write!(f, "Total amount overflow"),
+            ContractError::DepositBelowMinimum => {
+                write!(f, "The attached deposit is less than the minimum storage balance")
+            }
+            ContractError::InsufficientStorage(required) => {
+                write!(f, "Must attach {} yoctoNEAR to cover storage", required)
+            }
+            ContractError::PremiumNotCovered(premium) => write!(
+                f,
+                "The attached deposit doesn't cover the registration premium of {} yoctoNEAR",
+                premium
+            ),
+        }
+    }
+}
+
+/// Panics with `error` unless `condition` holds.
+pub(crate) fn require(condition: bool, error: ContractError) {
+    ensure(condition, error).unwrap_or_else(|error| error.panic())
+}
+
+/// Returns `error` unless `condition` holds.
+pub(crate) fn ensure(condition: bool, error: ContractError) -> Result<(), ContractError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_starts_with_code() {
+        assert_eq!(ContractError::Paused.to_string(), "ERR_PAUSED: Contract is paused");
+        assert_eq!(
+            ContractError::MemoTooLong(8).to_string(),
+            "ERR_MEMO_TOO_LONG: The memo can't be longer than 8 bytes"
+        );
+        assert_eq!(
+            ContractError::NotRegistered("alice".parse().unwrap()).to_string(),
+            "ERR_NOT_REGISTERED: The account alice is not registered"
+        );
+    }
+
+    #[test]
+    fn test_blacklist_errors_share_code() {
+        assert_eq!(ContractError::SenderBlacklisted.code(), "ERR_BLACKLISTED");
+        assert_eq!(ContractError::ReceiverBlacklisted.code(), "ERR_BLACKLISTED");
+    }
+}
@@ -0,0 +1,131 @@
+//! Events in the NEP-297 format. The events of the token administration are logged under the
+//! `ft_admin` standard:
+//!
+//! ```text
+//! EVENT_JSON:{"standard":"ft_admin","version":"1.0.0","event":"ownership_transferred","data":[...]}
+//! ```
+//!
+//! The `nep141` events reuse the data of `near_contract_standards`, but they're logged by
+//! `Nep141Event` with the version configured by the owner instead of the one of the standards
+//! crate, since some indexers expect a specific version.
+//!
+//! Calls that credit many accounts collect the credits in `BalanceChanges` and log them as a
+//! single `nep141` event at the end, with one entry per account.
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint, FtTransfer};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, serde_json, AccountId, Balance};
+
+/// Accumulates the amounts credited to accounts during a call. Repeated credits of an account are
+/// merged, and the accounts keep the order of their first credit.
+#[derive(Default)]
+pub(crate) struct BalanceChanges {
+    changes: Vec<(AccountId, U128)>,
+}
+
+impl BalanceChanges {
+    pub(crate) fn add(&mut self, account_id: &AccountId, amount: Balance) {
+        match self.changes.iter_mut().find(|(changed_id, _)| changed_id == account_id) {
+            Some((_, total)) => total.0 += amount,
+            None => self.changes.push((account_id.clone(), amount.into())),
+        }
+    }
+
+    /// Logs the credits as a single `ft_mint` event.
+    pub(crate) fn emit_mint(self, version: &str, memo: Option<&str>) {
+        let events: Vec<FtMint> = self
+            .changes
+            .iter()
+            .map(|(owner_id, amount)| FtMint { owner_id, amount, memo })
+            .collect();
+        Nep141Event::FtMint(&events).emit(version);
+    }
+
+    /// Logs the credits as a single `ft_transfer` event from `sender_id`.
+    pub(crate) fn emit_transfer(self, version: &str, sender_id: &AccountId, memo: Option<&str>) {
+        let events: Vec<FtTransfer> = self
+            .changes
+            .iter()
+            .map(|(new_owner_id, amount)| FtTransfer {
+                old_owner_id: sender_id,
+                new_owner_id,
+                amount,
+                memo,
+            })
+            .collect();
+        Nep141Event::FtTransfer(&events).emit(version);
+    }
+}
+
+/// The `nep141` events. To log an event, call `.emit(version)`.
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+// The variants are named after the events.
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum Nep141Event<'a> {
+    FtMint(&'a [FtMint<'a>]),
+    FtTransfer(&'a [FtTransfer<'a>]),
+    FtBurn(&'a [FtBurn<'a>]),
+}
+
+impl Nep141Event<'_> {
+    /// Logs the event to the host with the given `version`.
+    pub(crate) fn emit(self, version: &str) {
+        NearEvent::Nep141(EventData { version, event_kind: self }).emit()
+    }
+}
+
+/// Data to log when the owner changes. To log this event, call `.emit()`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferred<'a> {
+    pub old_owner: &'a AccountId,
+    pub new_owner: &'a AccountId,
+}
+
+impl OwnershipTransferred<'_> {
+    /// Logs the event to the host.
+    pub fn emit(self) {
+        NearEvent::FtAdmin(EventData {
+            version: "1.0.0",
+            event_kind: FtAdminEventKind::OwnershipTransferred(&[self]),
+        })
+        .emit()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "standard")]
+#[serde(rename_all = "snake_case")]
+enum NearEvent<'a> {
+    Nep141(EventData<'a, Nep141Event<'a>>),
+    FtAdmin(EventData<'a, FtAdminEventKind<'a>>),
+}
+
+impl NearEvent<'_> {
+    fn emit(self) {
+        // Events can't fail to serialize.
+        let json = serde_json::to_string(&self).unwrap();
+        env::log_str(&format!("EVENT_JSON:{}", json));
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct EventData<'a, K> {
+    version: &'a str,
+    #[serde(flatten)]
+    event_kind: K,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum FtAdminEventKind<'a> {
+    OwnershipTransferred(&'a [OwnershipTransferred<'a>]),
+}
@@ -0,0 +1,233 @@
+//! Transfer fees paid in another fungible token. When a fee token is set, `ft_transfer` doesn't
+//! move any tokens right away: it calls `ft_transfer_from` on the fee token to move
+//! `fee_flat_amount` from the sender to the treasury, and the transfer itself only happens in the
+//! `on_transfer_fee_paid` callback, once the fee was paid. If the fee payment fails, the transfer
+//! is dropped. If the fee was paid but the transfer no longer passes its checks, e.g. because the
+//! sender spent the tokens or was frozen in the meantime, the transfer is dropped as well and the
+//! fee isn't refunded: it stays with the treasury.
+//!
+//! The sender has to approve this contract as a spender of the fee on the fee token beforehand,
+//! and the fee token has to implement `ft_transfer_from`, like this contract does. Each sender can
+//! only have one such transfer waiting for its fee at a time, see the `guard` module.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Makes every `ft_transfer` pay a flat fee of `fee_flat_amount` in the `fee_token_id` token,
+    /// or disables the fee if `fee_token_id` is `None`. Can only be called by the owner.
+    pub fn set_fee_token(&mut self, fee_token_id: Option<AccountId>, fee_flat_amount: U128) {
+        self.assert_owner();
+        log!(
+            "Fee token set to {:?} with a fee of {} by @{}",
+            fee_token_id,
+            fee_flat_amount.0,
+            self.owner_id
+        );
+        self.fee_token_id = fee_token_id;
+        self.fee_flat_amount = fee_flat_amount.into();
+    }
+
+    /// Returns the token the transfer fee is paid in and the fee, or `None` if there's no fee.
+    pub fn get_fee_token(&self) -> Option<(AccountId, U128)> {
+        self.fee_token_id.clone().map(|fee_token_id| (fee_token_id, self.fee_flat_amount.into()))
+    }
+
+    /// Completes a transfer of `ft_transfer` if its fee was paid and it still passes the checks.
+    /// Everything that can make the transfer fail is checked before anything changes, so the
+    /// callback doesn't panic and always releases the guard of the sender. The memo was checked
+    /// when the transfer was started.
+    #[private]
+    pub fn on_transfer_fee_paid(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) -> bool {
+        self.finish_cross_contract_call(&sender_id);
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                if let Err(reason) = self.check_transfer(&sender_id, &receiver_id, amount.into()) {
+                    log!(
+                        "The transfer from @{} failed after its fee was paid, the fee isn't refunded: {}",
+                        sender_id,
+                        reason
+                    );
+                    return false;
+                }
+                self.internal_record_daily_transfer(&sender_id, amount.into());
+                self.internal_record_transfer_time(&sender_id);
+                self.internal_count_transfer(&sender_id);
+                self.internal_deduct_and_move(
+                    &sender_id,
+                    &receiver_id,
+                    amount.into(),
+                    memo.as_deref(),
+                );
+                true
+            }
+            _ => {
+                log!("The fee of the transfer from @{} wasn't paid, transfer dropped", sender_id);
+                false
+            }
+        }
+    }
+}
+
+impl Contract {
+    /// Pays the fee of a transfer in the fee token, then completes the transfer in
+    /// `on_transfer_fee_paid`. The transfer is checked upfront, so it doesn't pay a fee for a
+    /// transfer that would fail, but it's checked again when it's completed. Takes the reentrancy
+    /// guard of the sender until the callback runs.
+    pub(crate) fn internal_transfer_after_fee(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        let fee_token_id = self.fee_token_id.clone().unwrap();
+        if let Err(reason) = self.check_transfer(&sender_id, &receiver_id, amount.into()) {
+            env::panic_str(&reason);
+        }
+        self.assert_valid_memo(memo.as_deref());
+        self.start_cross_contract_call(&sender_id);
+        let fee_args = near_sdk::serde_json::json!({
+            "owner_id": sender_id,
+            "receiver_id": self.treasury_id,
+            "amount": U128(self.fee_flat_amount),
+            "memo": "fee",
+        });
+        let callback_args = near_sdk::serde_json::json!({
+            "sender_id": sender_id,
+            "receiver_id": receiver_id,
+            "amount": amount,
+            "memo": memo,
+        });
+        Promise::new(fee_token_id)
+            .function_call(
+                "ft_transfer_from".to_string(),
+                fee_args.to_string().into_bytes(),
+                1,
+                GAS_FOR_FEE_TOKEN_TRANSFER,
+            )
+            .then(Promise::new(env::current_account_id()).function_call(
+                "on_transfer_fee_paid".to_string(),
+                callback_args.to_string().into_bytes(),
+                0,
+                GAS_FOR_ON_FEE_PAID,
+            ));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, Balance};
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(contract.storage_balance_bounds().min.0).build());
+        contract.storage_deposit(Some(accounts(1)), None);
+        testing_env!(context.attached_deposit(0).build());
+        contract.set_fee_token(Some(accounts(4)), 10.into());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_pays_fee_in_fee_token() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.get_fee_token(), Some((accounts(4), 10.into())));
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, accounts(4));
+        assert!(format!("{:?}", receipts[0].actions).contains("ft_transfer_from"));
+        assert_eq!(receipts[1].receiver_id, accounts(0));
+        assert!(format!("{:?}", receipts[1].actions).contains("on_transfer_fee_paid"));
+        // Nothing moves until the fee is paid.
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert!(contract.is_in_progress(accounts(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CALL_IN_PROGRESS")]
+    fn test_transfer_while_fee_payment_in_progress() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_other_sender_can_transfer_while_fee_payment_in_progress() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        contract.internal_move_tokens(&accounts(2), &accounts(1), 1_000, None);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 500.into(), None);
+        assert!(contract.is_in_progress(accounts(1)));
+        assert!(contract.is_in_progress(accounts(2)));
+    }
+
+    #[test]
+    fn test_transfer_completes_after_fee_paid() {
+        let (mut context, mut contract) = setup();
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])]
+        );
+        contract.in_progress.insert(&accounts(2));
+        assert!(contract.on_transfer_fee_paid(accounts(2), accounts(1), 1_000.into(), None));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert!(!contract.is_in_progress(accounts(2)));
+    }
+
+    #[test]
+    fn test_transfer_dropped_after_fee_failed() {
+        let (mut context, mut contract) = setup();
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed]
+        );
+        contract.in_progress.insert(&accounts(2));
+        assert!(!contract.on_transfer_fee_paid(accounts(2), accounts(1), 1_000.into(), None));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert!(!contract.is_in_progress(accounts(2)));
+    }
+
+    #[test]
+    fn test_transfer_dropped_after_fee_paid_keeps_fee() {
+        let (mut context, mut contract) = setup();
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])]
+        );
+        contract.in_progress.insert(&accounts(2));
+        let amount = TOTAL_SUPPLY + 1;
+        assert!(!contract.on_transfer_fee_paid(accounts(2), accounts(1), amount.into(), None));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert!(!contract.is_in_progress(accounts(2)));
+        assert!(near_sdk::test_utils::get_logs()[0].contains("the fee isn't refunded"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Token-weighted governance proposals. Every vote is weighted by the voter's balance at the
+//! time of voting, and each account can vote once per proposal. Tokens transferred after voting
+//! can be voted with again by the receiver, so binding decisions should use a balance snapshot.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+/// The largest number of proposals returned by `proposals_page` at once.
+const MAX_PROPOSALS_PAGE_LEN: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Proposal {
+    pub description: String,
+    pub created_at: u64,
+    pub voting_ends: u64,
+    pub yes_votes: Balance,
+    pub no_votes: Balance,
+    pub voters: UnorderedSet<AccountId>,
+}
+
+impl Proposal {
+    /// Returns the status of the proposal at `timestamp`.
+    fn status(&self, timestamp: u64) -> ProposalStatus {
+        if timestamp < self.voting_ends {
+            ProposalStatus::Active
+        } else if self.yes_votes > self.no_votes {
+            ProposalStatus::Passed
+        } else {
+            ProposalStatus::Rejected
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalStatus {
+    /// Voting hasn't ended yet.
+    Active,
+    /// Voting has ended with more yes than no votes.
+    Passed,
+    /// Voting has ended without more yes than no votes.
+    Rejected,
+}
+
+/// A proposal with its tallies and current status.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalView {
+    pub id: u64,
+    pub description: String,
+    pub yes_votes: U128,
+    pub no_votes: U128,
+    pub voting_ends: U64,
+    pub status: ProposalStatus,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Creates a proposal that can be voted on for `duration_ns` nanoseconds and returns its id.
+    /// The attached deposit has to cover the storage of the proposal, the rest is refunded.
+    #[payable]
+    pub fn create_proposal(&mut self, description: String, duration_ns: U64) -> u64 {
+        assert!(duration_ns.0 > 0, "The duration should be a positive number");
+        let initial_storage_usage = env::storage_usage();
+        let proposal_id = self.proposals.len();
+        let mut prefix = b"g".to_vec();
+        prefix.extend(proposal_id.to_le_bytes());
+        let created_at = env::block_timestamp();
+        self.proposals.push(&Proposal {
+            description,
+            created_at,
+            voting_ends: created_at.saturating_add(duration_ns.0),
+            yes_votes: 0,
+            no_votes: 0,
+            voters: UnorderedSet::new(prefix),
+        });
+        settle_storage_deposit(initial_storage_usage);
+        proposal_id
+    }
+
+    /// Votes for or against the proposal with the predecessor's balance. The attached deposit
+    /// has to cover the storage of the vote, the rest is refunded.
+    #[payable]
+    pub fn vote(&mut self, proposal_id: u64, approve: bool) {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let mut proposal = self.proposals.get(proposal_id).expect("Proposal not found");
+        assert!(env::block_timestamp() < proposal.voting_ends, "The voting has ended");
+        let weight = self.token.accounts.get(&account_id).unwrap_or(0);
+        assert!(weight > 0, "Only token holders can vote");
+        assert!(proposal.voters.insert(&account_id), "The account has already voted");
+        if approve {
+            proposal.yes_votes += weight;
+        } else {
+            proposal.no_votes += weight;
+        }
+        self.proposals.replace(proposal_id, &proposal);
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    /// Returns the status of the proposal.
+    pub fn proposal_result(&self, proposal_id: u64) -> ProposalStatus {
+        let proposal = self.proposals.get(proposal_id).expect("Proposal not found");
+        proposal.status(env::block_timestamp())
+    }
+
+    /// Returns up to `limit` proposals starting at the id `from_index`, oldest first. At most 50
+    /// proposals are returned at once.
+    pub fn proposals_page(&self, from_index: u64, limit: u64) -> Vec<ProposalView> {
+        let limit = std::cmp::min(limit, MAX_PROPOSALS_PAGE_LEN);
+        let end = std::cmp::min(from_index.saturating_add(limit), self.proposals.len());
+        let timestamp = env::block_timestamp();
+        (from_index..end)
+            .map(|id| {
+                let proposal = self.proposals.get(id).unwrap();
+                ProposalView {
+                    id,
+                    status: proposal.status(timestamp),
+                    description: proposal.description,
+                    yes_votes: proposal.yes_votes.into(),
+                    no_votes: proposal.no_votes.into(),
+                    voting_ends: proposal.voting_ends.into(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+    const DURATION: u64 = 1_000;
+
+    fn setup() -> (VMContextBuilder, Contract, u64) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 4).into(), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        let proposal_id =
+            contract.create_proposal("Burn the treasury".to_string(), DURATION.into());
+        (context, contract, proposal_id)
+    }
+
+    fn vote(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        account_id: AccountId,
+        approve: bool,
+    ) {
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(account_id)
+            .build());
+        contract.vote(0, approve);
+    }
+
+    #[test]
+    fn test_passing_proposal() {
+        let (mut context, mut contract, proposal_id) = setup();
+        vote(&mut context, &mut contract, accounts(2), true);
+        vote(&mut context, &mut contract, accounts(1), false);
+        assert_eq!(contract.proposal_result(proposal_id), ProposalStatus::Active);
+
+        testing_env!(context.block_timestamp(DURATION).build());
+        assert_eq!(contract.proposal_result(proposal_id), ProposalStatus::Passed);
+    }
+
+    #[test]
+    fn test_failing_proposal() {
+        let (mut context, mut contract, proposal_id) = setup();
+        vote(&mut context, &mut contract, accounts(2), false);
+        vote(&mut context, &mut contract, accounts(1), true);
+
+        testing_env!(context.block_timestamp(DURATION).build());
+        assert_eq!(contract.proposal_result(proposal_id), ProposalStatus::Rejected);
+    }
+
+    #[test]
+    fn test_proposals_page() {
+        let (mut context, mut contract, _) = setup();
+        vote(&mut context, &mut contract, accounts(2), true);
+        testing_env!(context.block_timestamp(DURATION / 2).build());
+        contract.create_proposal("Raise the fee".to_string(), DURATION.into());
+        contract.create_proposal("Lower the fee".to_string(), DURATION.into());
+
+        testing_env!(context.block_timestamp(DURATION).build());
+        let page = contract.proposals_page(0, 2);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].id, 0);
+        assert_eq!(page[0].yes_votes.0, TOTAL_SUPPLY * 3 / 4);
+        assert_eq!(page[0].status, ProposalStatus::Passed);
+        assert_eq!(page[1].description, "Raise the fee");
+        assert_eq!(page[1].voting_ends.0, DURATION * 3 / 2);
+        assert_eq!(page[1].status, ProposalStatus::Active);
+
+        let page = contract.proposals_page(2, 2);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, 2);
+        assert!(contract.proposals_page(3, 2).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The account has already voted")]
+    fn test_double_vote() {
+        let (mut context, mut contract, _) = setup();
+        vote(&mut context, &mut contract, accounts(1), true);
+        vote(&mut context, &mut contract, accounts(1), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "The voting has ended")]
+    fn test_vote_after_voting_ends() {
+        let (mut context, mut contract, _) = setup();
+        context.block_timestamp(DURATION);
+        vote(&mut context, &mut contract, accounts(1), true);
+    }
+}
@@ -0,0 +1,33 @@
+//! Reentrancy guard of the flows that complete in a callback. While a flow of a sender waits for
+//! its callback, the sender is in `in_progress` and any other call of the same sender starting
+//! one of these flows is rejected, so the callback never runs on a balance that the sender changed
+//! in the meantime. Other senders aren't affected.
+//!
+//! Flows using the guard:
+//! - `ft_transfer` while a fee token is set: taken when the fee payment is scheduled, released in
+//!   `on_transfer_fee_paid`.
+//!
+//! The `on_supply_change` notification of the registry has no callback and `ft_transfer_call`
+//! resolves against the balances it reads in `ft_resolve_transfer`, so they don't take the guard.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Returns whether a flow of `account_id` waits for its callback.
+    pub fn is_in_progress(&self, account_id: AccountId) -> bool {
+        self.in_progress.contains(&account_id)
+    }
+}
+
+impl Contract {
+    /// Marks a flow of `account_id` as waiting for its callback. Panics if another one already is.
+    pub(crate) fn start_cross_contract_call(&mut self, account_id: &AccountId) {
+        require(self.in_progress.insert(account_id), ContractError::CallInProgress);
+    }
+
+    /// Marks the flow of `account_id` waiting for its callback as done. The callback must not
+    /// panic after calling it, otherwise the guard would stay taken.
+    pub(crate) fn finish_cross_contract_call(&mut self, account_id: &AccountId) {
+        self.in_progress.remove(account_id);
+    }
+}
@@ -0,0 +1,142 @@
+//! History of the total supply. Every mint and burn records `(block_timestamp, total_supply)`.
+//! The history keeps the latest `max_history_len` entries: it's stored as a ring buffer, so once
+//! it's full every new entry replaces the oldest one.
+use crate::*;
+
+/// The number of supply history entries kept by default.
+pub const DEFAULT_MAX_HISTORY_LEN: u64 = 1_000;
+
+#[near_bindgen]
+impl Contract {
+    /// Returns up to `limit` supply history entries, oldest first, starting at `from_index`.
+    /// Index 0 is the oldest entry that is still kept.
+    pub fn supply_history_page(&self, from_index: u64, limit: u64) -> Vec<(U64, U128)> {
+        let len = self.supply_history.len();
+        let end = std::cmp::min(from_index.saturating_add(limit), len);
+        (from_index..end)
+            .map(|index| {
+                let (timestamp, total_supply) =
+                    self.supply_history.get((self.supply_history_start + index) % len).unwrap();
+                (timestamp.into(), total_supply.into())
+            })
+            .collect()
+    }
+
+    /// Sets the number of supply history entries to keep and drops the oldest entries past it.
+    /// Can only be called by the owner.
+    pub fn set_max_history_len(&mut self, max_history_len: u64) {
+        self.assert_owner();
+        let len = self.supply_history.len();
+        let kept = std::cmp::min(len, max_history_len);
+        let entries: Vec<(u64, Balance)> = (len - kept..len)
+            .map(|index| {
+                self.supply_history.get((self.supply_history_start + index) % len).unwrap()
+            })
+            .collect();
+        self.supply_history.clear();
+        self.supply_history.extend(entries);
+        self.supply_history_start = 0;
+        self.max_history_len = max_history_len;
+        log!("Maximum supply history length set to {} by @{}", max_history_len, self.owner_id);
+    }
+
+    /// Returns the number of supply history entries that are kept.
+    pub fn get_max_history_len(&self) -> u64 {
+        self.max_history_len
+    }
+}
+
+impl Contract {
+    /// Appends the current total supply to the history, replacing the oldest entry once the
+    /// history is full.
+    pub(crate) fn internal_record_supply(&mut self) {
+        let entry = (env::block_timestamp(), self.token.total_supply);
+        let len = self.supply_history.len();
+        if len < self.max_history_len {
+            self.supply_history.push(&entry);
+        } else if len > 0 {
+            self.supply_history.replace(self.supply_history_start, &entry);
+            self.supply_history_start = (self.supply_history_start + 1) % len;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(10);
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        (context, contract)
+    }
+
+    fn entries(contract: &Contract) -> Vec<(u64, Balance)> {
+        contract
+            .supply_history_page(0, 100)
+            .into_iter()
+            .map(|(timestamp, total_supply)| (timestamp.0, total_supply.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_history_on_mint_and_burn() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(entries(&contract), vec![(10, TOTAL_SUPPLY)]);
+
+        testing_env!(context.block_timestamp(20).build());
+        contract.ft_mint(accounts(2), 500.into(), None);
+        testing_env!(context.block_timestamp(30).attached_deposit(1).build());
+        contract.ft_burn(200.into(), None);
+        assert_eq!(
+            entries(&contract),
+            vec![(10, TOTAL_SUPPLY), (20, TOTAL_SUPPLY + 500), (30, TOTAL_SUPPLY + 300)]
+        );
+    }
+
+    #[test]
+    fn test_history_paging() {
+        let (mut context, mut contract) = setup();
+        for timestamp in 1..=4 {
+            testing_env!(context.block_timestamp(10 + timestamp).build());
+            contract.ft_mint(accounts(2), 1.into(), None);
+        }
+        let page = contract.supply_history_page(1, 2);
+        assert_eq!(
+            page,
+            vec![(11.into(), (TOTAL_SUPPLY + 1).into()), (12.into(), (TOTAL_SUPPLY + 2).into())]
+        );
+        assert_eq!(contract.supply_history_page(4, 10).len(), 1);
+        assert!(contract.supply_history_page(5, 10).is_empty());
+    }
+
+    #[test]
+    fn test_history_drops_oldest_entries() {
+        let (mut context, mut contract) = setup();
+        contract.set_max_history_len(3);
+        for timestamp in 1..=4 {
+            testing_env!(context.block_timestamp(10 + timestamp).build());
+            contract.ft_mint(accounts(2), 1.into(), None);
+        }
+        assert_eq!(
+            entries(&contract),
+            vec![(12, TOTAL_SUPPLY + 2), (13, TOTAL_SUPPLY + 3), (14, TOTAL_SUPPLY + 4)]
+        );
+
+        contract.set_max_history_len(2);
+        assert_eq!(entries(&contract), vec![(13, TOTAL_SUPPLY + 3), (14, TOTAL_SUPPLY + 4)]);
+        testing_env!(context.block_timestamp(15).build());
+        contract.ft_mint(accounts(2), 1.into(), None);
+        assert_eq!(entries(&contract), vec![(14, TOTAL_SUPPLY + 4), (15, TOTAL_SUPPLY + 5)]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Hooks that validate transfers before any balance is changed.
+//!
+//! Every transfer path (`ft_transfer`, `ft_transfer_call`, `ft_transfer_batch` and
+//! `ft_transfer_from`) runs the hook returned by `Contract::transfer_hook`. A hook rejects a
+//! transfer by returning an error from `check_transfer`, which makes `before_transfer` panic.
+//! `simulate_transfer` calls `check_transfer` to report the error instead, so both see the same
+//! checks. Hooks are composed with tuples: `(A, B)` runs `A` and then `B`.
+//!
+//! The hooks are compiled into the contract, there is no way to install one at runtime. To plug
+//! in a custom check, implement `TransferHook` for a type in this module, and append it to the
+//! chain in `Contract::transfer_hook`. E.g. with an `AmountAboveMaximum` variant added to
+//! `ContractError`:
+//!
+//! ```ignore
+//! struct MaxAmountHook {
+//!     max_amount: Balance,
+//! }
+//!
+//! impl TransferHook for MaxAmountHook {
+//!     fn check_transfer(
+//!         &self,
+//!         _sender_id: &AccountId,
+//!         _receiver_id: &AccountId,
+//!         amount: Balance,
+//!     ) -> Result<(), ContractError> {
+//!         ensure(amount <= self.max_amount, ContractError::AmountAboveMaximum)
+//!     }
+//! }
+//!
+//! fn transfer_hook(&self) -> impl TransferHook + '_ {
+//!     (
+//!         (
+//!             // The hooks that are already in the chain.
+//!         ),
+//!         MaxAmountHook { max_amount: 1_000 },
+//!     )
+//! }
+//! ```
+use near_sdk::collections::UnorderedSet;
+use near_sdk::{AccountId, Balance};
+
+use crate::errors::{ensure, ContractError};
+
+pub trait TransferHook {
+    /// Returns why `amount` can't be moved from `sender_id` to `receiver_id`, if it can't.
+    /// Allows every transfer by default.
+    fn check_transfer(
+        &self,
+        _sender_id: &AccountId,
+        _receiver_id: &AccountId,
+        _amount: Balance,
+    ) -> Result<(), ContractError> {
+        Ok(())
+    }
+}
+
+/// Runs a hook before a transfer. Implemented for every `TransferHook`, so a hook can't check
+/// anything that `check_transfer`, and with it `simulate_transfer`, doesn't.
+pub trait BeforeTransfer {
+    /// Called before `amount` is moved from `sender_id` to `receiver_id`.
+    /// Panics if `check_transfer` rejects the transfer.
+    fn before_transfer(&self, sender_id: &AccountId, receiver_id: &AccountId, amount: Balance);
+}
+
+impl<H: TransferHook> BeforeTransfer for H {
+    fn before_transfer(&self, sender_id: &AccountId, receiver_id: &AccountId, amount: Balance) {
+        if let Err(error) = self.check_transfer(sender_id, receiver_id, amount) {
+            error.panic()
+        }
+    }
+}
+
+/// Allows every transfer, e.g. as the end of an empty chain.
+impl TransferHook for () {}
+
+impl<A: TransferHook, B: TransferHook> TransferHook for (A, B) {
+    fn check_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), ContractError> {
+        self.0.check_transfer(sender_id, receiver_id, amount)?;
+        self.1.check_transfer(sender_id, receiver_id, amount)
+    }
+}
+
+/// Rejects all transfers while the contract is paused.
+pub struct PauseHook {
+    pub paused: bool,
+}
+
+impl TransferHook for PauseHook {
+    fn check_transfer(
+        &self,
+        _sender_id: &AccountId,
+        _receiver_id: &AccountId,
+        _amount: Balance,
+    ) -> Result<(), ContractError> {
+        ensure(!self.paused, ContractError::Paused)
+    }
+}
+
+/// Rejects all transfers while a snapshot is recorded, so its pages see the same balances.
+pub struct SnapshotHook {
+    pub recording: bool,
+}
+
+impl TransferHook for SnapshotHook {
+    fn check_transfer(
+        &self,
+        _sender_id: &AccountId,
+        _receiver_id: &AccountId,
+        _amount: Balance,
+    ) -> Result<(), ContractError> {
+        ensure(!self.recording, ContractError::SnapshotInProgress)
+    }
+}
+
+/// Rejects transfers from or to a blacklisted account.
+pub struct BlacklistHook<'a> {
+    pub blacklist: &'a UnorderedSet<AccountId>,
+}
+
+impl TransferHook for BlacklistHook<'_> {
+    fn check_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        _amount: Balance,
+    ) -> Result<(), ContractError> {
+        ensure(!self.blacklist.contains(sender_id), ContractError::SenderBlacklisted)?;
+        ensure(!self.blacklist.contains(receiver_id), ContractError::ReceiverBlacklisted)
+    }
+}
+
+/// Rejects transfers from a frozen account. Frozen accounts can still receive tokens.
+pub struct FreezeHook<'a> {
+    pub frozen_senders: &'a UnorderedSet<AccountId>,
+}
+
+impl TransferHook for FreezeHook<'_> {
+    fn check_transfer(
+        &self,
+        sender_id: &AccountId,
+        _receiver_id: &AccountId,
+        _amount: Balance,
+    ) -> Result<(), ContractError> {
+        ensure(!self.frozen_senders.contains(sender_id), ContractError::SenderFrozen)
+    }
+}
+
+/// Rejects transfers from or to an account that isn't whitelisted, unless `enabled` is off.
+/// `owner_id` is always treated as whitelisted.
+pub struct WhitelistHook<'a> {
+    pub enabled: bool,
+    pub whitelist: &'a UnorderedSet<AccountId>,
+    pub owner_id: &'a AccountId,
+}
+
+impl WhitelistHook<'_> {
+    fn is_whitelisted(&self, account_id: &AccountId) -> bool {
+        account_id == self.owner_id || self.whitelist.contains(account_id)
+    }
+}
+
+impl TransferHook for WhitelistHook<'_> {
+    fn check_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        _amount: Balance,
+    ) -> Result<(), ContractError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        ensure(self.is_whitelisted(sender_id), ContractError::SenderNotWhitelisted)?;
+        ensure(self.is_whitelisted(receiver_id), ContractError::ReceiverNotWhitelisted)
+    }
+}
+
+/// Rejects transfers of less than `min_amount` tokens. A `min_amount` of 0 allows every amount.
+pub struct MinAmountHook {
+    pub min_amount: Balance,
+}
+
+impl TransferHook for MinAmountHook {
+    fn check_transfer(
+        &self,
+        _sender_id: &AccountId,
+        _receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), ContractError> {
+        ensure(amount >= self.min_amount, ContractError::AmountBelowMinimum)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    struct MaxAmountHook {
+        max_amount: Balance,
+    }
+
+    impl TransferHook for MaxAmountHook {
+        fn check_transfer(
+            &self,
+            _sender_id: &AccountId,
+            _receiver_id: &AccountId,
+            amount: Balance,
+        ) -> Result<(), ContractError> {
+            ensure(amount <= self.max_amount, ContractError::AmountOverflow)
+        }
+    }
+
+    #[test]
+    fn test_noop_hook() {
+        ((), MaxAmountHook { max_amount: 100 }).before_transfer(&accounts(1), &accounts(2), 100);
+    }
+
+    #[test]
+    fn test_threshold_hook_is_simulated() {
+        let hook = (PauseHook { paused: false }, MaxAmountHook { max_amount: 100 });
+        assert_eq!(hook.check_transfer(&accounts(1), &accounts(2), 100), Ok(()));
+        assert_eq!(
+            hook.check_transfer(&accounts(1), &accounts(2), 101),
+            Err(ContractError::AmountOverflow)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_OVERFLOW")]
+    fn test_threshold_hook() {
+        (PauseHook { paused: false }, MaxAmountHook { max_amount: 100 }).before_transfer(
+            &accounts(1),
+            &accounts(2),
+            101,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED: Contract is paused")]
+    fn test_chain_stops_at_first_rejection() {
+        (PauseHook { paused: true }, MaxAmountHook { max_amount: 100 }).before_transfer(
+            &accounts(1),
+            &accounts(2),
+            101,
+        );
+    }
+}
@@ -14,23 +14,198 @@ NOTES:
     attach more deposit than required.
   - To prevent the deployed contract from being modified or deleted, it should not have any access
     keys on its account.
+  - Failed transfers, mints and storage deposits panic with a message that starts with a stable
+    error code, e.g. `ERR_PAUSED: Contract is paused`. The codes are listed in `errors.rs`.
 */
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint, FtTransfer};
 use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
 };
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
 use near_contract_standards::fungible_token::FungibleToken;
+use near_contract_standards::storage_management::{
+    StorageBalance, StorageBalanceBounds, StorageManagement,
+};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
-use near_sdk::json_types::U128;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseOrValue};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::{
+    assert_one_yocto, env, log, near_bindgen, AccountId, Balance, Gas, PanicOnDefault, Promise,
+    PromiseOrValue, PromiseResult, StorageUsage,
+};
+use std::collections::HashSet;
+
+mod action;
+mod allowance;
+mod auto_register;
+#[cfg(feature = "benchmarks")]
+mod bench;
+mod burn;
+mod closed;
+mod config;
+mod errors;
+mod events;
+mod fee_token;
+mod governance;
+mod guard;
+mod history;
+mod hook;
+mod lock;
+mod math;
+mod meta;
+mod metadata;
+mod migrate;
+mod mint_keys;
+mod pause;
+mod pending;
+#[cfg(feature = "admin_recovery")]
+mod recovery;
+mod registration;
+mod rewards;
+mod signed_metadata;
+mod simulate;
+mod snapshot;
+mod sweep;
+mod timelock;
+mod version;
+mod wrap;
+
+use crate::action::route_transfer_msg;
+use crate::burn::BurnMode;
+use crate::closed::ClosedRecord;
+use crate::config::InitConfig;
+use crate::errors::{ensure, require, ContractError};
+use crate::events::{BalanceChanges, Nep141Event, OwnershipTransferred};
+use crate::governance::Proposal;
+use crate::history::DEFAULT_MAX_HISTORY_LEN;
+use crate::hook::{
+    BeforeTransfer, BlacklistHook, FreezeHook, MinAmountHook, PauseHook, SnapshotHook,
+    TransferHook, WhitelistHook,
+};
+use crate::lock::LockInfo;
+use crate::math::mul_div;
+use crate::pause::PauseFlags;
+use crate::pending::PendingTransfer;
+use crate::rewards::RewardDistribution;
+use crate::snapshot::Snapshot;
+use crate::timelock::{AdminAction, QueuedAction};
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
+    owner_id: AccountId,
+    pause_flags: PauseFlags,
+    blacklist: UnorderedSet<AccountId>,
+    max_supply: Option<Balance>,
+    holders_count: u64,
+    minters: UnorderedSet<AccountId>,
+    treasury_id: AccountId,
+    fee_basis_points: u16,
+    burn_basis_points: u16,
+    allowances: LookupMap<(AccountId, AccountId), Balance>,
+    locks: LookupMap<AccountId, LockInfo>,
+    holders: UnorderedSet<AccountId>,
+    snapshots: Vector<Snapshot>,
+    snapshot_balances: LookupMap<(u64, AccountId), Balance>,
+    excluded_accounts: UnorderedSet<AccountId>,
+    locked_total: Balance,
+    pending_owner: Option<AccountId>,
+    min_transfer_amount: Balance,
+    mint_cap_per_epoch: Balance,
+    epoch_length_ns: u64,
+    current_epoch: u64,
+    minted_this_epoch: Balance,
+    admin_actions: Vector<Option<QueuedAction>>,
+    max_memo_len: u32,
+    closed_accounts: UnorderedMap<AccountId, ClosedRecord>,
+    proposals: Vector<Proposal>,
+    registry_id: Option<AccountId>,
+    wrappable: bool,
+    daily_limit: Option<Balance>,
+    daily_transfers: LookupMap<AccountId, (u64, Balance)>,
+    whitelist_mode: bool,
+    whitelist: UnorderedSet<AccountId>,
+    supply_history: Vector<(u64, Balance)>,
+    supply_history_start: u64,
+    max_history_len: u64,
+    auto_pause_threshold_bps: Option<u16>,
+    signing_keys: LookupMap<AccountId, [u8; 32]>,
+    nonces: LookupMap<AccountId, u64>,
+    registration_premium: Balance,
+    extra_metadata: UnorderedMap<String, FungibleTokenMetadata>,
+    fee_token_id: Option<AccountId>,
+    fee_flat_amount: Balance,
+    transfer_cooldown_ns: u64,
+    last_transfers: LookupMap<AccountId, u64>,
+    mint_deadline_ns: Option<u64>,
+    frozen_senders: UnorderedSet<AccountId>,
+    rename_cooldown_ns: u64,
+    last_rename_ts: Option<u64>,
+    guardian_id: Option<AccountId>,
+    event_version: String,
+    burn_mode: BurnMode,
+    in_progress: LookupSet<AccountId>,
+    max_batch_size: u32,
+    reward_distribution: Option<RewardDistribution>,
+    allow_auto_register: bool,
+    total_minted: Balance,
+    total_burned: Balance,
+    require_memo: bool,
+    metadata_authority_pk: Option<[u8; 32]>,
+    metadata_nonce: u64,
+    pending_transfers: LookupMap<u64, PendingTransfer>,
+    next_pending_id: u64,
+    pending_balances: LookupMap<AccountId, Balance>,
+    fee_round_up: bool,
+    metadata_locked: bool,
+    transfer_counts: LookupMap<AccountId, u64>,
+    ft_on_transfer_gas: Option<Gas>,
+    transfer_call_allowlist: UnorderedSet<AccountId>,
+    emit_events: bool,
+    fee_recipients: Vec<(AccountId, u16)>,
+    mint_keys: UnorderedSet<String>,
+    holders_indexed: bool,
+    legacy_account_storage_usage: Option<StorageUsage>,
+    legacy_accounts: LookupSet<AccountId>,
 }
 
+/// The largest transfer fee that can be configured, in basis points (10%).
+const MAX_FEE_BASIS_POINTS: u16 = 1_000;
+/// The largest share of a transfer that can be burned, in basis points (20%).
+const MAX_BURN_BASIS_POINTS: u16 = 2_000;
+/// The length of a day for the daily transfer limit, in nanoseconds.
+const DAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+/// The largest number of accounts that can be queried by `ft_balances_of` at once.
+const MAX_BALANCES_QUERY_LEN: usize = 100;
+/// The largest number of recipients of `ft_transfer_batch` and `ft_mint_batch` until the owner
+/// changes it.
+const DEFAULT_MAX_BATCH_SIZE: u32 = 100;
+/// The largest number of decimals a token can be initialized with, the same as NEAR itself.
+const MAX_DECIMALS: u8 = 24;
+/// The default longest memo of a transfer, in bytes.
+const MAX_MEMO_LEN: u32 = 256;
+/// The default minimum time between two renames of the token.
+const DEFAULT_RENAME_COOLDOWN_NS: u64 = 30 * DAY_NS;
+/// Gas attached to the `on_supply_change` notification of the registry.
+const GAS_FOR_SUPPLY_CHANGE: Gas = Gas(5_000_000_000_000);
+/// Gas attached to the `ft_transfer_from` call that pays the fee in the fee token.
+const GAS_FOR_FEE_TOKEN_TRANSFER: Gas = Gas(10_000_000_000_000);
+/// Gas attached to the callback that completes a transfer once its fee is paid.
+const GAS_FOR_ON_FEE_PAID: Gas = Gas(20_000_000_000_000);
+/// Gas attached to the `ft_transfer` call of `sweep_foreign_token`.
+const GAS_FOR_SWEEP: Gas = Gas(10_000_000_000_000);
+/// Gas attached to the call that fails a transfer after it tripped the circuit breaker.
+const GAS_FOR_AUTO_PAUSE_REJECTION: Gas = Gas(5_000_000_000_000);
+/// Gas reserved for `ft_resolve_transfer`, the callback of `ft_transfer_call`.
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+/// Gas kept by `ft_transfer_call` for itself and the callback, the rest goes to `ft_on_transfer`.
+const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+/// The version of the `nep141` events, unless the owner overrides it.
+const DEFAULT_EVENT_VERSION: &str = "1.0.0";
+
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg width='373' height='373' viewBox='0 0 373 373' fill='none' xmlns='http://www.w3.org/2000/svg'%3E%3Cg filter='url(%23filter0_ii_2016_186744)'%3E%3Ccircle cx='186.432' cy='186.432' r='173.432' fill='url(%23paint0_linear_2016_186744)'/%3E%3C/g%3E%3Ccircle cx='186.432' cy='186.432' r='179.856' stroke='url(%23paint1_linear_2016_186744)' stroke-width='12.8468'/%3E%3Cpath fill-rule='evenodd' clip-rule='evenodd' d='M218.981 204.243H231.074L272.216 233.785H292.045L251.038 204.243H259.536C264.033 204.243 268.259 203.389 272.216 201.68C276.173 199.972 279.59 197.679 282.468 194.801C285.435 191.833 287.774 188.371 289.482 184.414C291.191 180.457 292.045 176.231 292.045 171.734C292.045 167.238 291.191 163.056 289.482 159.189C287.774 155.233 285.435 151.815 282.468 148.938C279.59 145.97 276.173 143.632 272.216 141.923C268.259 140.215 264.033 139.36 259.536 139.36H174.015V151.096H259.536C262.414 151.096 265.112 151.636 267.63 152.715C270.148 153.794 272.306 155.278 274.105 157.166C275.993 159.055 277.477 161.258 278.556 163.776C279.635 166.204 280.175 168.857 280.175 171.734C280.175 174.612 279.635 177.31 278.556 179.828C277.477 182.346 275.993 184.549 274.105 186.438C272.306 188.326 270.148 189.81 267.63 190.889C265.112 191.968 262.414 192.508 259.536 192.508H203.439L218.981 204.243Z' fill='white'/%3E%3Cpath d='M231.074 204.243L234.821 199.026L233.141 197.82H231.074V204.243ZM218.981 204.243L215.11 209.37L216.828 210.667H218.981V204.243ZM272.216 233.785L268.47 239.002L270.149 240.208H272.216V233.785ZM292.045 233.785V240.208H311.951L295.8 228.573L292.045 233.785ZM251.038 204.243V197.82H231.132L247.283 209.455L251.038 204.243ZM272.216 201.68L274.763 207.578L274.763 207.578L272.216 201.68ZM282.468 194.801L287.01 199.343V199.343L282.468 194.801ZM289.482 184.414L283.585 181.868V181.868L289.482 184.414ZM289.482 159.189L283.585 161.736L283.596 161.761L283.607 161.786L289.482 159.189ZM282.468 148.938L277.856 153.409L277.925 153.48L277.996 153.549L282.468 148.938ZM272.216 141.923L274.763 136.026L274.763 136.026L272.216 141.923ZM174.015 139.36V132.937H167.591V139.36H174.015ZM174.015 151.096H167.591V157.519H174.015V151.096ZM267.63 152.715L265.099 158.619L265.099 158.619L267.63 152.715ZM274.105 157.166L269.453 161.596L269.507 161.653L269.563 161.708L274.105 157.166ZM278.556 163.776L272.652 166.306L272.669 166.345L272.686 166.385L278.556 163.776ZM274.105 186.438L269.563 181.896L269.507 181.951L269.453 182.008L274.105 186.438ZM267.63 190.889L265.099 184.985L265.099 184.985L267.63 190.889ZM203.439 192.508V186.084H184.273L199.568 197.634L203.439 192.508ZM231.074 197.82H218.981V210.667H231.074V197.82ZM275.963 228.567L234.821 199.026L227.328 209.461L268.47 239.002L275.963 228.567ZM292.045 227.361H272.216V240.208H292.045V227.361ZM247.283 209.455L288.291 238.997L295.8 228.573L254.793 199.032L247.283 209.455ZM259.536 197.82H251.038V210.667H259.536V197.82ZM269.67 195.783C266.552 197.13 263.195 197.82 259.536 197.82V210.667C264.87 210.667 269.967 209.648 274.763 207.578L269.67 195.783ZM277.926 190.259C275.655 192.53 272.926 194.377 269.67 195.783L274.763 207.578C279.42 205.566 283.525 202.828 287.01 199.343L277.926 190.259ZM283.585 181.868C282.189 185.1 280.304 187.88 277.926 190.259L287.01 199.343C290.567 195.786 293.358 191.642 295.379 186.961L283.585 181.868ZM285.622 171.734C285.622 175.394 284.932 178.75 283.585 181.868L295.379 186.961C297.45 182.165 298.469 177.068 298.469 171.734H285.622ZM283.607 161.786C284.931 164.783 285.622 168.074 285.622 171.734H298.469C298.469 166.402 297.45 161.329 295.358 156.593L283.607 161.786ZM277.996 153.549C280.332 155.814 282.197 158.521 283.585 161.736L295.379 156.643C293.35 151.945 290.539 147.817 286.939 144.326L277.996 153.549ZM269.67 147.82C272.885 149.209 275.592 151.074 277.856 153.409L287.079 144.466C283.589 140.866 279.461 138.055 274.763 136.026L269.67 147.82ZM259.536 145.784C263.195 145.784 266.552 146.474 269.67 147.82L274.763 136.026C269.967 133.955 264.87 132.937 259.536 132.937V145.784ZM174.015 145.784H259.536V132.937H174.015V145.784ZM180.438 151.096V139.36H167.591V151.096H180.438ZM259.536 144.673H174.015V157.519H259.536V144.673ZM270.16 146.811C266.802 145.372 263.239 144.673 259.536 144.673V157.519C261.589 157.519 263.421 157.899 265.099 158.619L270.16 146.811ZM278.756 152.736C276.327 150.186 273.437 148.215 270.16 146.811L265.099 158.619C266.859 159.373 268.285 160.369 269.453 161.596L278.756 152.736ZM284.46 161.245C283.067 157.996 281.128 155.105 278.647 152.624L269.563 161.708C270.858 163.004 271.886 164.519 272.652 166.306L284.46 161.245ZM286.598 171.734C286.598 168.034 285.899 164.483 284.426 161.167L272.686 166.385C273.371 167.925 273.751 169.68 273.751 171.734H286.598ZM284.46 182.358C285.899 179.001 286.598 175.437 286.598 171.734H273.751C273.751 173.787 273.371 175.619 272.652 177.298L284.46 182.358ZM278.647 190.98C281.128 188.498 283.067 185.608 284.46 182.358L272.652 177.298C271.886 179.084 270.858 180.6 269.563 181.896L278.647 190.98ZM270.16 196.793C273.437 195.389 276.327 193.418 278.756 190.868L269.453 182.008C268.285 183.235 266.859 184.231 265.099 184.985L270.16 196.793ZM259.536 198.931C263.239 198.931 266.802 198.232 270.16 196.793L265.099 184.985C263.421 185.704 261.589 186.084 259.536 186.084V198.931ZM203.439 198.931H259.536V186.084H203.439V198.931ZM199.568 197.634L215.11 209.37L222.852 199.117L207.31 187.382L199.568 197.634Z' fill='white'/%3E%3Cpath fill-rule='evenodd' clip-rule='evenodd' d='M183.412 209.779C183.239 211.107 182.873 212.393 182.314 213.635C181.595 215.434 180.561 217.007 179.212 218.356C177.863 219.705 176.289 220.784 174.49 221.594C172.692 222.313 170.758 222.673 168.69 222.673H103.807C101.829 222.673 99.9402 222.313 98.1416 221.594C96.3431 220.784 94.7693 219.705 93.4204 218.356C92.0715 217.007 90.9924 215.434 90.183 213.635C89.4636 211.837 89.1039 209.948 89.1039 207.97V140.119H77.2334V207.97C77.2334 211.657 77.9079 215.119 79.2568 218.356C80.6956 221.594 82.5841 224.426 84.9222 226.855C87.3503 229.193 90.183 231.081 93.4204 232.52C96.6578 233.869 100.12 234.543 103.807 234.543H168.69C172.377 234.543 175.839 233.869 179.077 232.52C182.314 231.081 185.102 229.193 187.44 226.855C189.868 224.426 191.757 221.594 193.106 218.356C193.221 218.098 193.331 217.837 193.437 217.576L183.412 209.779Z' fill='white'/%3E%3Cpath d='M182.314 213.635L176.457 210.999L176.401 211.123L176.35 211.25L182.314 213.635ZM183.412 209.779L187.356 204.708L178.491 197.814L177.042 208.95L183.412 209.779ZM179.212 218.356L183.754 222.898L183.754 222.898L179.212 218.356ZM174.49 221.594L176.876 227.558L177.002 227.507L177.126 227.451L174.49 221.594ZM98.1416 221.594L95.5057 227.451L95.6298 227.507L95.7561 227.558L98.1416 221.594ZM93.4204 218.356L88.8784 222.898L88.8784 222.898L93.4204 218.356ZM90.183 213.635L84.219 216.021L84.2695 216.147L84.3254 216.271L90.183 213.635ZM89.1039 140.119H95.5273V133.696H89.1039V140.119ZM77.2334 140.119V133.696H70.81V140.119H77.2334ZM79.2568 218.356L73.3275 220.827L73.3564 220.896L73.387 220.965L79.2568 218.356ZM84.9222 226.855L80.2953 231.31L80.3794 231.397L80.4667 231.481L84.9222 226.855ZM93.4204 232.52L90.8116 238.39L90.8804 238.42L90.9499 238.449L93.4204 232.52ZM179.077 232.52L181.547 238.449L181.617 238.42L181.686 238.39L179.077 232.52ZM193.106 218.356L187.236 215.748L187.205 215.816L187.176 215.886L193.106 218.356ZM193.437 217.576L199.392 219.984L201.211 215.485L197.38 212.505L193.437 217.576ZM188.172 216.271C188.985 214.464 189.526 212.571 189.782 210.607L177.042 208.95C176.952 209.643 176.762 210.321 176.457 210.999L188.172 216.271ZM183.754 222.898C185.727 220.925 187.241 218.614 188.278 216.021L176.35 211.25C175.949 212.253 175.394 213.09 174.67 213.814L183.754 222.898ZM177.126 227.451C179.61 226.334 181.836 224.816 183.754 222.898L174.67 213.814C173.89 214.594 172.968 215.235 171.855 215.736L177.126 227.451ZM168.69 229.096C171.52 229.096 174.269 228.601 176.876 227.558L172.105 215.63C171.115 216.026 169.997 216.249 168.69 216.249V229.096ZM103.807 229.096H168.69V216.249H103.807V229.096ZM95.7561 227.558C98.3297 228.587 101.029 229.096 103.807 229.096V216.249C102.628 216.249 101.551 216.039 100.527 215.63L95.7561 227.558ZM88.8784 222.898C90.7963 224.816 93.0217 226.334 95.5057 227.451L100.778 215.736C99.6644 215.235 98.7424 214.594 97.9625 213.814L88.8784 222.898ZM84.3254 216.271C85.4431 218.755 86.9604 220.98 88.8784 222.898L97.9625 213.814C97.1826 213.034 96.5416 212.112 96.0407 210.999L84.3254 216.271ZM82.6805 207.97C82.6805 210.747 83.1896 213.447 84.219 216.021L96.147 211.25C95.7376 210.226 95.5273 209.149 95.5273 207.97H82.6805ZM82.6805 140.119V207.97H95.5273V140.119H82.6805ZM77.2334 146.542H89.1039V133.696H77.2334V146.542ZM83.6568 207.97V140.119H70.81V207.97H83.6568ZM85.1861 215.886C84.1799 213.471 83.6568 210.853 83.6568 207.97H70.81C70.81 212.461 71.6358 216.767 73.3275 220.827L85.1861 215.886ZM89.5492 222.399C87.7557 220.537 86.2774 218.337 85.1266 215.748L73.387 220.965C75.1138 224.851 77.4125 228.316 80.2953 231.31L89.5492 222.399ZM96.0292 226.65C93.4398 225.499 91.2402 224.021 89.3778 222.228L80.4667 231.481C83.4604 234.364 86.9262 236.663 90.8116 238.39L96.0292 226.65ZM103.807 228.12C100.924 228.12 98.3057 227.597 95.891 226.591L90.9499 238.449C95.0099 240.141 99.3162 240.967 103.807 240.967V228.12ZM168.69 228.12H103.807V240.967H168.69V228.12ZM176.606 226.591C174.191 227.597 171.573 228.12 168.69 228.12V240.967C173.181 240.967 177.487 240.141 181.547 238.449L176.606 226.591ZM182.898 222.312C181.156 224.054 179.037 225.509 176.468 226.65L181.686 238.39C185.592 236.654 189.048 234.331 191.982 231.397L182.898 222.312ZM187.176 215.886C186.143 218.366 184.72 220.491 182.898 222.312L191.982 231.397C195.016 228.362 197.37 224.822 199.035 220.827L187.176 215.886ZM187.482 215.167C187.403 215.361 187.321 215.555 187.236 215.748L198.975 220.965C199.12 220.64 199.259 220.313 199.392 219.984L187.482 215.167ZM197.38 212.505L187.356 204.708L179.469 214.849L189.493 222.646L197.38 212.505Z' fill='white'/%3E%3Cdefs%3E%3Cfilter id='filter0_ii_2016_186744' x='0.153198' y='-24.8468' width='372.558' height='423.252' filterUnits='userSpaceOnUse' color-interpolation-filters='sRGB'%3E%3CfeFlood flood-opacity='0' result='BackgroundImageFix'/%3E%3CfeBlend mode='normal' in='SourceGraphic' in2='BackgroundImageFix' result='shape'/%3E%3CfeColorMatrix in='SourceAlpha' type='matrix' values='0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 127 0' result='hardAlpha'/%3E%3CfeOffset dy='25.6937'/%3E%3CfeGaussianBlur stdDeviation='12.8468'/%3E%3CfeComposite in2='hardAlpha' operator='arithmetic' k2='-1' k3='1'/%3E%3CfeColorMatrix type='matrix' values='0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0.25 0'/%3E%3CfeBlend mode='normal' in2='shape' result='effect1_innerShadow_2016_186744'/%3E%3CfeColorMatrix in='SourceAlpha' type='matrix' values='0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 127 0' result='hardAlpha'/%3E%3CfeOffset dy='-25'/%3E%3CfeGaussianBlur stdDeviation='12.5'/%3E%3CfeComposite in2='hardAlpha' operator='arithmetic' k2='-1' k3='1'/%3E%3CfeColorMatrix type='matrix' values='0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0.25 0'/%3E%3CfeBlend mode='normal' in2='effect1_innerShadow_2016_186744' result='effect2_innerShadow_2016_186744'/%3E%3C/filter%3E%3ClinearGradient id='paint0_linear_2016_186744' x1='186.432' y1='13' x2='186.432' y2='359.865' gradientUnits='userSpaceOnUse'%3E%3Cstop stop-color='%234388DD'/%3E%3Cstop offset='1' stop-color='%23C60286'/%3E%3C/linearGradient%3E%3ClinearGradient id='paint1_linear_2016_186744' x1='186.432' y1='13' x2='186.432' y2='359.865' gradientUnits='userSpaceOnUse'%3E%3Cstop stop-color='white'/%3E%3Cstop offset='0.494792' stop-color='%23808080'/%3E%3Cstop offset='1' stop-color='white'/%3E%3C/linearGradient%3E%3C/defs%3E%3C/svg%3E%0A";
 
 #[near_bindgen]
@@ -38,7 +213,12 @@ impl Contract {
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
     /// default metadata (for example purposes only).
     #[init]
-    pub fn new_default_meta(owner_id: AccountId, total_supply: U128) -> Self {
+    pub fn new_default_meta(
+        owner_id: AccountId,
+        total_supply: U128,
+        max_supply: Option<U128>,
+        owner_lock_duration_ns: Option<U64>,
+    ) -> Self {
         Self::new(
             owner_id,
             total_supply,
@@ -51,118 +231,4184 @@ impl Contract {
                 reference_hash: None,
                 decimals: 6,
             },
+            Some(InitConfig { max_supply, owner_lock_duration_ns, ..Default::default() }),
         )
     }
 
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// the given fungible token metadata.
+    /// the given fungible token metadata. The optional settings are passed in `config`, see
+    /// `InitConfig`, and all default to off. Panics if the metadata has more than 24 decimals.
     #[init]
     pub fn new(
         owner_id: AccountId,
         total_supply: U128,
         metadata: FungibleTokenMetadata,
+        config: Option<InitConfig>,
     ) -> Self {
+        let InitConfig {
+            max_supply,
+            treasury_id,
+            fee_basis_points,
+            burn_basis_points,
+            expected_decimals,
+            wrappable,
+            mint_deadline_ns,
+            burn_mode,
+            owner_lock_duration_ns,
+            allow_zero_supply,
+        } = config.unwrap_or_default();
         assert!(!env::state_exists(), "Already initialized");
         metadata.assert_valid();
+        assert_valid_decimals(metadata.decimals, expected_decimals);
+        assert_valid_fee(fee_basis_points);
+        assert_valid_burn(burn_basis_points);
+        assert!(!wrappable || total_supply.0 == 0, "Wrapped NEAR has to start with zero supply");
+        assert!(
+            total_supply.0 > 0 || allow_zero_supply || wrappable,
+            "The total supply should be a positive number unless allow_zero_supply is set"
+        );
+        if let Some(max_supply) = max_supply {
+            assert!(total_supply.0 <= max_supply.0, "The total supply can't exceed the max supply");
+        }
+        let treasury_id = treasury_id.unwrap_or_else(|| owner_id.clone());
         let mut this = Self {
             token: FungibleToken::new(b"a".to_vec()),
             metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            owner_id: owner_id.clone(),
+            pause_flags: PauseFlags::default(),
+            blacklist: UnorderedSet::new(b"b".to_vec()),
+            max_supply: max_supply.map(|max_supply| max_supply.into()),
+            holders_count: 0,
+            minters: UnorderedSet::new(b"n".to_vec()),
+            treasury_id: treasury_id.clone(),
+            fee_basis_points,
+            burn_basis_points,
+            allowances: LookupMap::new(b"l".to_vec()),
+            locks: LookupMap::new(b"v".to_vec()),
+            holders: UnorderedSet::new(b"h".to_vec()),
+            snapshots: Vector::new(b"s".to_vec()),
+            snapshot_balances: LookupMap::new(b"t".to_vec()),
+            excluded_accounts: UnorderedSet::new(b"x".to_vec()),
+            locked_total: 0,
+            pending_owner: None,
+            min_transfer_amount: 0,
+            mint_cap_per_epoch: 0,
+            epoch_length_ns: 0,
+            current_epoch: 0,
+            minted_this_epoch: 0,
+            admin_actions: Vector::new(b"q".to_vec()),
+            max_memo_len: MAX_MEMO_LEN,
+            closed_accounts: UnorderedMap::new(b"c".to_vec()),
+            proposals: Vector::new(b"p".to_vec()),
+            registry_id: None,
+            wrappable,
+            daily_limit: None,
+            daily_transfers: LookupMap::new(b"d".to_vec()),
+            whitelist_mode: false,
+            whitelist: UnorderedSet::new(b"w".to_vec()),
+            supply_history: Vector::new(b"r".to_vec()),
+            supply_history_start: 0,
+            max_history_len: DEFAULT_MAX_HISTORY_LEN,
+            auto_pause_threshold_bps: None,
+            signing_keys: LookupMap::new(b"k".to_vec()),
+            nonces: LookupMap::new(b"o".to_vec()),
+            registration_premium: 0,
+            extra_metadata: UnorderedMap::new(b"e".to_vec()),
+            fee_token_id: None,
+            fee_flat_amount: 0,
+            transfer_cooldown_ns: 0,
+            last_transfers: LookupMap::new(b"f".to_vec()),
+            mint_deadline_ns: mint_deadline_ns.map(|mint_deadline_ns| mint_deadline_ns.into()),
+            frozen_senders: UnorderedSet::new(b"i".to_vec()),
+            rename_cooldown_ns: DEFAULT_RENAME_COOLDOWN_NS,
+            last_rename_ts: None,
+            guardian_id: None,
+            event_version: DEFAULT_EVENT_VERSION.to_string(),
+            burn_mode,
+            in_progress: LookupSet::new(b"ip".to_vec()),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            reward_distribution: None,
+            allow_auto_register: false,
+            total_minted: total_supply.into(),
+            total_burned: 0,
+            require_memo: false,
+            metadata_authority_pk: None,
+            metadata_nonce: 0,
+            pending_transfers: LookupMap::new(b"j".to_vec()),
+            next_pending_id: 0,
+            pending_balances: LookupMap::new(b"u".to_vec()),
+            fee_round_up: false,
+            metadata_locked: false,
+            transfer_counts: LookupMap::new(b"y".to_vec()),
+            ft_on_transfer_gas: None,
+            transfer_call_allowlist: UnorderedSet::new(b"z".to_vec()),
+            emit_events: true,
+            fee_recipients: Vec::new(),
+            mint_keys: UnorderedSet::new(b"mk".to_vec()),
+            holders_indexed: true,
+            legacy_account_storage_usage: None,
+            legacy_accounts: LookupSet::new(b"la".to_vec()),
         };
-        this.token.internal_register_account(&owner_id);
+        this.measure_account_storage_usage();
+        this.internal_register_account(&owner_id);
+        if treasury_id != owner_id {
+            this.internal_register_account(&treasury_id);
+        }
+        if let Some(dead_id) = this.dead_account() {
+            if !this.token.accounts.contains_key(&dead_id) {
+                this.internal_register_account(&dead_id);
+            }
+        }
         this.token.internal_deposit(&owner_id, total_supply.into());
-        near_contract_standards::fungible_token::events::FtMint {
+        if let Some(duration_ns) = owner_lock_duration_ns {
+            this.internal_lock_owner_supply(duration_ns.into());
+        }
+        this.internal_record_supply();
+        this.emit_event(Nep141Event::FtMint(&[FtMint {
             owner_id: &owner_id,
             amount: &total_supply,
             memo: Some("Initial tokens supply is minted"),
-        }
-        .emit();
+        }]));
         this
     }
 
-    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
-        log!("Closed @{} with {}", account_id, balance);
+    /// Mints `amount` new tokens to `account_id`. Can only be called by the owner or a minter.
+    /// If `account_id` isn't registered yet, it gets registered in the same call, and the
+    /// attached deposit has to cover the minimum storage balance and the registration premium,
+    /// like `storage_deposit`. The unused part of the attached deposit is refunded.
+    #[payable]
+    pub fn ft_mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        self.assert_minter();
+        self.assert_not_wrappable();
+        self.assert_mints_not_paused();
+        self.assert_before_mint_deadline();
+        let mut deposit = env::attached_deposit();
+        if !self.token.accounts.contains_key(&account_id) {
+            deposit = self.internal_register_with_deposit(&account_id, deposit);
+        }
+        if deposit > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(deposit);
+        }
+        self.assert_within_max_supply(amount.into());
+        self.internal_record_mint(amount.into());
+        self.token.internal_deposit(&account_id, amount.into());
+        self.total_minted += amount.0;
+        self.emit_event(Nep141Event::FtMint(&[FtMint {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: memo.as_deref(),
+        }]));
+        self.internal_record_supply();
+        self.notify_supply_change();
     }
 
-    fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
-        log!("Account @{} burned {}", account_id, amount);
+    /// Same as `ft_mint`, but `whole_units` is in whole tokens, e.g. 5 mints 5000000 base units
+    /// of a token with 6 decimals. Can only be called by the owner or a minter.
+    #[payable]
+    pub fn ft_mint_whole(
+        &mut self,
+        account_id: AccountId,
+        whole_units: U128,
+        memo: Option<String>,
+    ) {
+        let unit = 10u128.pow(u32::from(self.metadata.get().unwrap().decimals));
+        let amount = whole_units
+            .0
+            .checked_mul(unit)
+            .unwrap_or_else(|| ContractError::AmountOverflow.panic());
+        self.ft_mint(account_id, amount.into(), memo);
     }
-}
 
-near_contract_standards::impl_fungible_token_core!(Contract, token, on_tokens_burned);
-near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+    /// Mints `amounts[i]` tokens to `accounts[i]` for every recipient, e.g. for an airdrop.
+    /// Unregistered recipients are registered, the attached deposit has to cover the minimum
+    /// storage balance and the registration premium of each of them and the rest is refunded. Emits
+    /// a single `ft_mint` event that lists every recipient. At most `max_batch_size` recipients can
+    /// be minted to at once. Can only be called by the owner or a minter.
+    #[payable]
+    pub fn ft_mint_batch(&mut self, accounts: Vec<AccountId>, amounts: Vec<U128>) {
+        self.assert_minter();
+        self.assert_not_wrappable();
+        self.assert_mints_not_paused();
+        self.assert_before_mint_deadline();
+        require(!accounts.is_empty(), ContractError::EmptyBatch);
+        self.assert_valid_batch_len(accounts.len());
+        require(accounts.len() == amounts.len(), ContractError::BatchLengthMismatch);
+        let unregistered: HashSet<&AccountId> = accounts
+            .iter()
+            .filter(|account_id| !self.token.accounts.contains_key(account_id))
+            .collect();
+        let storage_cost =
+            self.token.storage_balance_bounds().min.0 * unregistered.len() as Balance;
+        let premium = self.registration_premium * unregistered.len() as Balance;
+        let deposit = env::attached_deposit();
+        require(
+            deposit >= storage_cost + premium,
+            ContractError::InsufficientStorage(storage_cost + premium),
+        );
+        let total_amount =
+            amounts.iter().try_fold(0, |total: Balance, amount| total.checked_add(amount.0));
+        let total_amount = total_amount.unwrap_or_else(|| ContractError::AmountOverflow.panic());
+        self.assert_within_max_supply(total_amount);
 
-#[near_bindgen]
-impl FungibleTokenMetadataProvider for Contract {
-    fn ft_metadata(&self) -> FungibleTokenMetadata {
-        self.metadata.get().unwrap()
+        self.internal_record_mint(total_amount);
+
+        for account_id in unregistered {
+            self.internal_register_account(account_id);
+        }
+        self.internal_forward_premium(premium);
+        let mut changes = BalanceChanges::default();
+        for (account_id, amount) in accounts.iter().zip(amounts.iter()) {
+            self.token.internal_deposit(account_id, amount.0);
+            changes.add(account_id, amount.0);
+        }
+        self.total_minted += total_amount;
+        let refund = deposit - storage_cost - premium;
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        if self.emit_events {
+            changes.emit_mint(&self.event_version, None);
+        }
+        self.internal_record_supply();
+        self.notify_supply_change();
     }
-}
 
-#[cfg(all(test, not(target_arch = "wasm32")))]
-mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, Balance};
+    /// Burns `amount` tokens from the predecessor's balance, reducing the total supply.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_wrappable();
+        self.assert_burns_not_paused();
+        let account_id = env::predecessor_account_id();
+        self.assert_spendable(&account_id, amount.into());
+        if let Some(dead_id) = self.dead_account() {
+            self.internal_move_tokens(&account_id, &dead_id, amount.into(), memo.as_deref());
+            return;
+        }
+        self.token.internal_withdraw(&account_id, amount.into());
+        self.emit_event(Nep141Event::FtBurn(&[FtBurn {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: memo.as_deref(),
+        }]));
+        self.on_tokens_burned(account_id, amount.into());
+        self.notify_supply_change();
+    }
 
-    use super::*;
+    /// Same as `ft_transfer`, but returns the balance of the predecessor after the transfer.
+    #[payable]
+    pub fn ft_transfer_checked(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) -> U128 {
+        self.ft_transfer(receiver_id, amount, memo);
+        self.token.ft_balance_of(env::predecessor_account_id())
+    }
 
-    const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+    /// Transfers `amounts[i]` tokens from the predecessor to `recipients[i]` for every `i` in one
+    /// call. All recipients have to be registered. At most `max_batch_size` recipients can be
+    /// transferred to at once. Emits a single `ft_transfer` event that lists every transfer,
+    /// unless there's a transfer fee or burn: every transfer is then charged like an
+    /// `ft_transfer` and logged on its own.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn ft_transfer_batch(
+        &mut self,
+        recipients: Vec<AccountId>,
+        amounts: Vec<U128>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        require(!recipients.is_empty(), ContractError::EmptyBatch);
+        self.assert_valid_batch_len(recipients.len());
+        require(recipients.len() == amounts.len(), ContractError::BatchLengthMismatch);
+        self.assert_valid_memo(memo.as_deref());
+        let sender_id = env::predecessor_account_id();
+        let mut total_amount: Balance = 0;
+        let mut total_debit: Balance = 0;
+        for (receiver_id, amount) in recipients.iter().zip(amounts.iter()) {
+            self.assert_transfer_allowed(&sender_id, receiver_id, amount.0);
+            require(receiver_id != &sender_id, ContractError::SameAccount);
+            require(amount.0 > 0, ContractError::ZeroAmount);
+            require(
+                self.token.accounts.contains_key(receiver_id),
+                ContractError::NotRegistered(receiver_id.clone()),
+            );
+            total_amount = total_amount
+                .checked_add(amount.0)
+                .unwrap_or_else(|| ContractError::AmountOverflow.panic());
+            total_debit = self
+                .transfer_debit(&sender_id, receiver_id, amount.0)
+                .and_then(|debit| {
+                    total_debit.checked_add(debit).ok_or(ContractError::AmountOverflow)
+                })
+                .unwrap_or_else(|error| error.panic());
+        }
+        self.assert_spendable(&sender_id, total_debit);
+        if let Some(rejection) =
+            recipients.iter().zip(amounts.iter()).find_map(|(receiver_id, amount)| {
+                self.trip_circuit_breaker(&sender_id, receiver_id, amount.0)
+            })
+        {
+            rejection.as_return();
+            return;
+        }
+        self.internal_record_daily_transfer(&sender_id, total_amount);
+        self.internal_record_transfer_time(&sender_id);
+        self.internal_count_transfer(&sender_id);
 
-    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
-        let mut builder = VMContextBuilder::new();
-        builder
-            .current_account_id(accounts(0))
-            .signer_account_id(predecessor_account_id.clone())
-            .predecessor_account_id(predecessor_account_id);
-        builder
+        let charged = recipients.iter().zip(amounts.iter()).any(|(receiver_id, amount)| {
+            self.transfer_deductions(&sender_id, receiver_id, amount.0) != (0, 0)
+        });
+        if charged {
+            for (receiver_id, amount) in recipients.iter().zip(amounts.iter()) {
+                self.internal_deduct_and_move(&sender_id, receiver_id, amount.0, memo.as_deref());
+            }
+            return;
+        }
+        self.token.internal_withdraw(&sender_id, total_amount);
+        let mut changes = BalanceChanges::default();
+        for (receiver_id, amount) in recipients.iter().zip(amounts.iter()) {
+            self.token.internal_deposit(receiver_id, amount.0);
+            changes.add(receiver_id, amount.0);
+        }
+        if self.emit_events {
+            changes.emit_transfer(&self.event_version, &sender_id, memo.as_deref());
+        }
     }
 
-    #[test]
-    fn test_new() {
-        let mut context = get_context(accounts(1));
-        testing_env!(context.build());
-        let contract = Contract::new_default_meta(accounts(1).into(), TOTAL_SUPPLY.into());
-        testing_env!(context.is_view(true).build());
-        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
-        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
+    /// Returns the balances of `account_ids` in the same order. Unregistered accounts have a zero
+    /// balance. At most 100 accounts can be queried at once.
+    pub fn ft_balances_of(&self, account_ids: Vec<AccountId>) -> Vec<U128> {
+        assert!(
+            account_ids.len() <= MAX_BALANCES_QUERY_LEN,
+            "At most {} accounts can be queried at once",
+            MAX_BALANCES_QUERY_LEN
+        );
+        account_ids.into_iter().map(|account_id| self.token.ft_balance_of(account_id)).collect()
     }
 
-    #[test]
-    #[should_panic(expected = "The contract is not initialized")]
-    fn test_default() {
-        let context = get_context(accounts(1));
-        testing_env!(context.build());
-        let _contract = Contract::default();
+    /// Returns the balance of `account_id` as a decimal string in whole tokens, using the
+    /// `decimals` of the metadata, e.g. "1.5" for a balance of 1500000 with 6 decimals.
+    pub fn ft_balance_of_formatted(&self, account_id: AccountId) -> String {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        format_amount(balance, self.metadata.get().unwrap().decimals)
     }
 
-    #[test]
-    fn test_transfer() {
-        let mut context = get_context(accounts(2));
-        testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(contract.storage_balance_bounds().min.into())
-            .predecessor_account_id(accounts(1))
-            .build());
-        // Paying for account registration, aka storage deposit
-        contract.storage_deposit(None, None);
+    /// Rounds `amount` down to `keep_decimals` fractional digits, using the `decimals` of the
+    /// metadata, e.g. 1230000 for 1234567 with 6 decimals and `keep_decimals` of 2. Amounts
+    /// with no more than `keep_decimals` fractional digits are returned unchanged.
+    pub fn round_amount(&self, amount: U128, keep_decimals: u8) -> U128 {
+        round_down(amount.0, self.metadata.get().unwrap().decimals, keep_decimals).into()
+    }
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(1)
-            .predecessor_account_id(accounts(2))
-            .build());
-        let transfer_amount = TOTAL_SUPPLY / 3;
-        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+    /// Registers every account of `account_ids` that isn't registered yet, e.g. to onboard users
+    /// in one transaction. The attached deposit has to cover the minimum storage balance and the
+    /// registration premium of each new account, the rest is refunded to the caller. Registered
+    /// accounts are skipped for free.
+    #[payable]
+    pub fn storage_deposit_batch(&mut self, account_ids: Vec<AccountId>) {
+        let unregistered: HashSet<AccountId> = account_ids
+            .into_iter()
+            .filter(|account_id| !self.token.accounts.contains_key(account_id))
+            .collect();
+        let storage_cost =
+            self.token.storage_balance_bounds().min.0 * unregistered.len() as Balance;
+        let premium = self.registration_premium * unregistered.len() as Balance;
+        let deposit = env::attached_deposit();
+        require(
+            deposit >= storage_cost + premium,
+            ContractError::InsufficientStorage(storage_cost + premium),
+        );
+        for account_id in unregistered.iter() {
+            self.internal_register_account(account_id);
+        }
+        self.internal_forward_premium(premium);
+        let refund = deposit - storage_cost - premium;
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .account_balance(env::account_balance())
-            .is_view(true)
-            .attached_deposit(0)
-            .build());
-        assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - transfer_amount));
-        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
+    /// Returns whether `account_id` is registered with the token.
+    pub fn is_registered(&self, account_id: AccountId) -> bool {
+        self.token.accounts.contains_key(&account_id)
+    }
+
+    /// Returns the deposit needed to register `receiver_id` before it can receive tokens, the
+    /// minimum storage balance plus the registration premium, or `None` if it's registered.
+    pub fn transfer_requires_registration(&self, receiver_id: AccountId) -> Option<U128> {
+        if self.token.accounts.contains_key(&receiver_id) {
+            return None;
+        }
+        Some((self.token.storage_balance_bounds().min.0 + self.registration_premium).into())
+    }
+
+    /// Returns the number of accounts registered with the token.
+    pub fn ft_holders_count(&self) -> u64 {
+        self.holders_count
+    }
+
+    /// Returns up to `limit` registered accounts starting at `from_index`. The order is stable
+    /// while no account is unregistered.
+    pub fn list_accounts(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        let holders = self.holders.as_vector();
+        let end = std::cmp::min(from_index.saturating_add(limit), holders.len());
+        (from_index..end).map(|index| holders.get(index).unwrap()).collect()
+    }
+
+    /// Returns the number of transfers sent by `account_id` since it was registered.
+    pub fn transfer_count(&self, account_id: AccountId) -> u64 {
+        self.transfer_counts.get(&account_id).unwrap_or(0)
+    }
+
+    /// Returns the storage key of the balance of `account_id`, e.g. to look it up in the state of
+    /// the contract. Like `FungibleToken` does, it's the prefix of the balances, `a`, followed by
+    /// the Borsh serialization of the account ID.
+    pub fn account_storage_key(&self, account_id: AccountId) -> Base64VecU8 {
+        let mut key = b"a".to_vec();
+        key.extend(account_id.try_to_vec().unwrap());
+        key.into()
+    }
+
+    /// Queues a change of the share of every `ft_transfer` that is charged to the sender on top of
+    /// the transferred amount and goes to the treasury, in basis points, and returns the index of
+    /// the queued `AdminAction::SetFee`. The fee changes once the action is executed with
+    /// `execute_action` after the minimum delay. Can only be called by the owner.
+    pub fn set_fee_basis_points(&mut self, fee_basis_points: u16) -> u64 {
+        self.assert_owner();
+        self.internal_queue_action_with_min_delay(AdminAction::SetFee { fee_basis_points })
+    }
+
+    /// Sets the share of every `ft_transfer` that is burned, in basis points. The burned part is
+    /// charged to the sender on top of the transferred amount. Can only be called by the owner.
+    pub fn set_burn_basis_points(&mut self, burn_basis_points: u16) {
+        self.assert_owner();
+        assert_valid_burn(burn_basis_points);
+        log!("Transfer burn set to {} basis points by @{}", burn_basis_points, self.owner_id);
+        self.burn_basis_points = burn_basis_points;
+    }
+
+    /// Makes the transfer fee and the burned amount round up instead of down. Can only be called
+    /// by the owner.
+    pub fn set_fee_round_up(&mut self, round_up: bool) {
+        self.assert_owner();
+        self.fee_round_up = round_up;
+        log!("Fee rounding up set to {} by @{}", round_up, self.owner_id);
+    }
+
+    /// Returns whether the transfer fee and the burned amount round up.
+    pub fn is_fee_round_up(&self) -> bool {
+        self.fee_round_up
+    }
+
+    /// Returns the share of every transfer that is burned, in basis points.
+    pub fn get_burn_basis_points(&self) -> u16 {
+        self.burn_basis_points
+    }
+
+    /// Returns the transfer fee in basis points.
+    pub fn get_fee_basis_points(&self) -> u16 {
+        self.fee_basis_points
+    }
+
+    /// Returns the account that receives the transfer fees.
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury_id.clone()
+    }
+
+    /// Splits the transfer fees between `recipients` in proportion to their weights, which have
+    /// to add up to 10000. The rounding dust goes to the first recipient. An empty list sends the
+    /// whole fee to the treasury again. Every recipient has to be registered. Transfers from or to
+    /// a recipient are not charged, like those of the treasury. Can only be called by the owner.
+    pub fn set_fee_recipients(&mut self, recipients: Vec<(AccountId, u16)>) {
+        self.assert_owner();
+        if !recipients.is_empty() {
+            let total_weight: u32 = recipients.iter().map(|(_, weight)| u32::from(*weight)).sum();
+            assert!(total_weight == 10_000, "The weights of the fee recipients must sum to 10000");
+            let mut seen = HashSet::new();
+            for (account_id, _) in recipients.iter() {
+                assert!(seen.insert(account_id), "The fee recipient {} is repeated", account_id);
+                require(
+                    self.token.accounts.contains_key(account_id),
+                    ContractError::NotRegistered(account_id.clone()),
+                );
+            }
+        }
+        log!("Fee recipients set to {:?} by @{}", recipients, self.owner_id);
+        self.fee_recipients = recipients;
+    }
+
+    /// Returns the accounts the transfer fees are split between and their weights, empty if the
+    /// treasury receives the whole fee.
+    pub fn get_fee_recipients(&self) -> Vec<(AccountId, u16)> {
+        self.fee_recipients.clone()
+    }
+
+    /// Charges `premium` yoctoNEAR on top of the minimum storage balance for every registration,
+    /// which is forwarded to the treasury. Can only be called by the owner.
+    pub fn set_registration_premium(&mut self, premium: U128) {
+        self.assert_owner();
+        log!("Registration premium set to {} by @{}", premium.0, self.owner_id);
+        self.registration_premium = premium.into();
+    }
+
+    /// Returns the premium charged on top of the minimum storage balance for a registration.
+    pub fn get_registration_premium(&self) -> U128 {
+        self.registration_premium.into()
+    }
+
+    /// Rejects transfers of less than `amount` tokens, 0 allows any amount.
+    /// Can only be called by the owner.
+    pub fn set_min_transfer_amount(&mut self, amount: U128) {
+        self.assert_owner();
+        log!("Minimum transfer amount set to {} by @{}", amount.0, self.owner_id);
+        self.min_transfer_amount = amount.into();
+    }
+
+    /// Returns the smallest amount of tokens that can be transferred.
+    pub fn get_min_transfer_amount(&self) -> U128 {
+        self.min_transfer_amount.into()
+    }
+
+    /// Limits the amount of tokens that can be minted within one epoch.
+    /// Can only be called by the owner.
+    pub fn set_mint_cap_per_epoch(&mut self, mint_cap: U128) {
+        self.assert_owner();
+        log!("Mint cap per epoch set to {} by @{}", mint_cap.0, self.owner_id);
+        self.mint_cap_per_epoch = mint_cap.into();
+    }
+
+    /// Sets the length of the epochs that the mint cap applies to, 0 disables the mint cap.
+    /// Can only be called by the owner.
+    pub fn set_epoch_length(&mut self, epoch_length_ns: U64) {
+        self.assert_owner();
+        log!("Epoch length set to {} ns by @{}", epoch_length_ns.0, self.owner_id);
+        self.epoch_length_ns = epoch_length_ns.into();
+    }
+
+    /// Makes every transfer require a non-empty memo, e.g. for compliance, or lifts the
+    /// requirement. Can only be called by the owner.
+    pub fn set_require_memo(&mut self, required: bool) {
+        self.assert_owner();
+        self.require_memo = required;
+        log!("Memo requirement set to {} by @{}", required, self.owner_id);
+    }
+
+    /// Returns whether transfers require a non-empty memo.
+    pub fn is_memo_required(&self) -> bool {
+        self.require_memo
+    }
+
+    /// Sets the longest memo a transfer can have, in bytes. Can only be called by the owner.
+    pub fn set_max_memo_len(&mut self, max_memo_len: u32) {
+        self.assert_owner();
+        log!("Maximum memo length set to {} by @{}", max_memo_len, self.owner_id);
+        self.max_memo_len = max_memo_len;
+    }
+
+    /// Returns the longest memo a transfer can have, in bytes.
+    pub fn get_max_memo_len(&self) -> u32 {
+        self.max_memo_len
+    }
+
+    /// Sets the largest number of recipients of `ft_transfer_batch` and `ft_mint_batch`, e.g. to
+    /// fit the batches in the gas budget. Can only be called by the owner.
+    pub fn set_max_batch_size(&mut self, max_batch_size: u32) {
+        self.assert_owner();
+        assert!(max_batch_size > 0, "The maximum batch size should be positive");
+        log!("Maximum batch size set to {} by @{}", max_batch_size, self.owner_id);
+        self.max_batch_size = max_batch_size;
+    }
+
+    /// Returns the largest number of recipients of `ft_transfer_batch` and `ft_mint_batch`.
+    pub fn get_max_batch_size(&self) -> u32 {
+        self.max_batch_size
+    }
+
+    /// Sets the gas attached to the `ft_on_transfer` call of `ft_transfer_call`, e.g. for heavy
+    /// receivers. `None` attaches all the prepaid gas left, like the standard does.
+    /// Can only be called by the owner.
+    pub fn set_ft_on_transfer_gas(&mut self, gas: Option<U64>) {
+        self.assert_owner();
+        log!("ft_on_transfer gas set to {:?} by @{}", gas.map(|gas| gas.0), self.owner_id);
+        self.ft_on_transfer_gas = gas.map(|gas| Gas(gas.0));
+    }
+
+    /// Returns the gas attached to the `ft_on_transfer` call, or `None` if it gets all the
+    /// prepaid gas left.
+    pub fn get_ft_on_transfer_gas(&self) -> Option<U64> {
+        self.ft_on_transfer_gas.map(|gas| gas.0.into())
+    }
+
+    /// Sets the registry that is notified with `on_supply_change` whenever tokens are minted or
+    /// burned, `None` stops the notifications. Can only be called by the owner.
+    pub fn set_registry(&mut self, account_id: Option<AccountId>) {
+        self.assert_owner();
+        log!("Registry set to {:?} by @{}", account_id, self.owner_id);
+        self.registry_id = account_id;
+    }
+
+    /// Returns the registry that is notified about supply changes.
+    pub fn get_registry(&self) -> Option<AccountId> {
+        self.registry_id.clone()
+    }
+
+    /// Limits the amount of tokens every account can transfer per day, `None` removes the limit.
+    /// Can only be called by the owner.
+    pub fn set_daily_limit(&mut self, daily_limit: Option<U128>) {
+        self.assert_owner();
+        log!("Daily limit set to {:?} by @{}", daily_limit.map(|limit| limit.0), self.owner_id);
+        self.daily_limit = daily_limit.map(|daily_limit| daily_limit.into());
+    }
+
+    /// Returns the amount of tokens every account can transfer per day, or `None` if unlimited.
+    pub fn get_daily_limit(&self) -> Option<U128> {
+        self.daily_limit.map(|daily_limit| daily_limit.into())
+    }
+
+    /// Allows every account except the owner one transfer per `cooldown_ns` nanoseconds, zero
+    /// disables the cooldown. Can only be called by the owner.
+    pub fn set_transfer_cooldown(&mut self, cooldown_ns: U64) {
+        self.assert_owner();
+        log!("Transfer cooldown set to {} ns by @{}", cooldown_ns.0, self.owner_id);
+        self.transfer_cooldown_ns = cooldown_ns.into();
+    }
+
+    /// Returns the minimum time between two transfers of an account, in nanoseconds.
+    pub fn get_transfer_cooldown(&self) -> U64 {
+        self.transfer_cooldown_ns.into()
+    }
+
+    /// Pauses the contract automatically when a single transfer exceeds `threshold_bps` basis
+    /// points of the total supply, `None` turns the circuit breaker off. The transfer that trips
+    /// it moves nothing and fails with `ERR_AUTO_PAUSED`, but the contract stays paused.
+    /// Can only be called by the owner.
+    pub fn set_auto_pause_threshold(&mut self, threshold_bps: Option<u16>) {
+        self.assert_owner();
+        if let Some(threshold_bps) = threshold_bps {
+            assert!(threshold_bps <= 10_000, "The threshold can't exceed 10000 basis points");
+        }
+        log!("Auto-pause threshold set to {:?} by @{}", threshold_bps, self.owner_id);
+        self.auto_pause_threshold_bps = threshold_bps;
+    }
+
+    /// Returns the share of the total supply in basis points that a single transfer can move
+    /// without pausing the contract, or `None` if the circuit breaker is off.
+    pub fn get_auto_pause_threshold(&self) -> Option<u16> {
+        self.auto_pause_threshold_bps
+    }
+
+    /// Fails with `ERR_AUTO_PAUSED`. Returned by a transfer that tripped the circuit breaker, so
+    /// the transfer fails while the pause, which was saved by the transfer's own receipt, stays.
+    #[private]
+    pub fn reject_auto_paused_transfer(&self, threshold: U128) {
+        ContractError::AutoPaused(threshold.0).panic()
+    }
+
+    /// Moves the mint deadline to `mint_deadline_ns`. The deadline can only be moved earlier, so
+    /// holders can rely on it. Can only be called by the owner.
+    pub fn set_mint_deadline(&mut self, mint_deadline_ns: U64) {
+        self.assert_owner();
+        if let Some(current) = self.mint_deadline_ns {
+            assert!(mint_deadline_ns.0 <= current, "The mint deadline can only be moved earlier");
+        }
+        self.mint_deadline_ns = Some(mint_deadline_ns.into());
+        log!("Mint deadline set to {} by @{}", mint_deadline_ns.0, self.owner_id);
+    }
+
+    /// Returns the block timestamp after which no tokens can be minted, or `None` if minting
+    /// never ends.
+    pub fn mint_deadline(&self) -> Option<U64> {
+        self.mint_deadline_ns.map(|mint_deadline_ns| mint_deadline_ns.into())
+    }
+
+    /// Returns the amount of tokens minted since the contract was initialized, including the
+    /// initial supply.
+    pub fn total_minted(&self) -> U128 {
+        self.total_minted.into()
+    }
+
+    /// Returns the amount of tokens burned since the contract was initialized. The total supply
+    /// is always `total_minted - total_burned`.
+    pub fn total_burned(&self) -> U128 {
+        self.total_burned.into()
+    }
+
+    /// Returns the maximum total supply, or `None` if minting is unbounded.
+    pub fn get_max_supply(&self) -> Option<U128> {
+        self.max_supply.map(|max_supply| max_supply.into())
+    }
+
+    /// Returns the total supply without the balances of the excluded accounts and the tokens
+    /// that are still locked.
+    pub fn circulating_supply(&self) -> U128 {
+        let excluded: Balance = self
+            .excluded_accounts
+            .iter()
+            .map(|account_id| self.token.accounts.get(&account_id).unwrap_or(0))
+            .sum();
+        self.token.total_supply.saturating_sub(excluded).saturating_sub(self.locked_total).into()
+    }
+
+    /// Excludes the balance of `account_id` from the circulating supply.
+    /// Can only be called by the owner.
+    pub fn add_excluded_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.excluded_accounts.insert(&account_id) {
+            log!("Account @{} is excluded from the circulating supply", account_id);
+        }
+    }
+
+    /// Includes the balance of `account_id` in the circulating supply again.
+    /// Can only be called by the owner.
+    pub fn remove_excluded_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.excluded_accounts.remove(&account_id) {
+            log!("Account @{} is included in the circulating supply", account_id);
+        }
+    }
+
+    /// Returns the account that is allowed to call admin methods.
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// Queues the transfer of the ownership of the contract to `new_owner` and returns the index
+    /// of the queued `AdminAction::SetOwner`. The ownership is transferred once the action is
+    /// executed with `execute_action` after the minimum delay. Can only be called by the current
+    /// owner. Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn set_owner(&mut self, new_owner: AccountId) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_queue_action_with_min_delay(AdminAction::SetOwner { new_owner })
+    }
+
+    /// Proposes `new_owner` as the next owner, replacing any previous proposal. The ownership is
+    /// only transferred once `new_owner` calls `accept_owner`. Can only be called by the current
+    /// owner. Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn propose_owner(&mut self, new_owner: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Ownership transfer to @{} proposed", new_owner);
+        self.pending_owner = Some(new_owner);
+    }
+
+    /// Completes the ownership transfer. Can only be called by the pending owner.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn accept_owner(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.pending_owner.as_ref() == Some(&account_id),
+            "Only the pending owner can accept the ownership"
+        );
+        self.internal_set_owner(account_id);
+    }
+
+    /// Cancels the pending ownership transfer. Can only be called by the current owner.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn cancel_ownership_transfer(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(pending_owner) = self.pending_owner.take() {
+            log!("Ownership transfer to @{} cancelled", pending_owner);
+        }
+    }
+
+    /// Returns the account that can accept the ownership, if a transfer is pending.
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    /// Pauses all token transfers, mints and burns. Can only be called by the owner or the
+    /// guardian.
+    pub fn pause(&mut self) {
+        self.assert_owner_or_guardian();
+        self.internal_pause();
+    }
+
+    /// Resumes token transfers, mints and burns. Can only be called by the owner.
+    pub fn unpause(&mut self) {
+        self.assert_owner();
+        self.internal_unpause();
+    }
+
+    /// Returns whether token transfers are paused.
+    pub fn is_paused(&self) -> bool {
+        self.pause_flags.transfers
+    }
+
+    /// Blocks `account_id` from sending and receiving tokens. Can only be called by the owner.
+    pub fn blacklist_add(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.blacklist.insert(&account_id) {
+            log!("Account @{} is blacklisted", account_id);
+        }
+    }
+
+    /// Allows a blacklisted `account_id` to transfer tokens again. Can only be called by the owner.
+    pub fn blacklist_remove(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.blacklist.remove(&account_id) {
+            log!("Account @{} is removed from the blacklist", account_id);
+        }
+    }
+
+    /// Returns whether `account_id` is blacklisted.
+    pub fn is_blacklisted(&self, account_id: AccountId) -> bool {
+        self.blacklist.contains(&account_id)
+    }
+
+    /// Blocks `account_id` from sending tokens, while it can still receive them.
+    /// Can only be called by the owner.
+    pub fn freeze(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.frozen_senders.insert(&account_id) {
+            log!("Account @{} is frozen", account_id);
+        }
+    }
+
+    /// Allows a frozen `account_id` to send tokens again. Can only be called by the owner.
+    pub fn unfreeze(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.frozen_senders.remove(&account_id) {
+            log!("Account @{} is unfrozen", account_id);
+        }
+    }
+
+    /// Returns whether `account_id` is frozen.
+    pub fn is_frozen(&self, account_id: AccountId) -> bool {
+        self.frozen_senders.contains(&account_id)
+    }
+
+    /// Turns the whitelist mode on or off. While it's on, tokens can only be transferred between
+    /// whitelisted accounts and the owner. Can only be called by the owner.
+    pub fn set_whitelist_mode(&mut self, enabled: bool) {
+        self.assert_owner();
+        self.whitelist_mode = enabled;
+        log!("Whitelist mode set to {} by @{}", enabled, self.owner_id);
+    }
+
+    /// Returns whether the whitelist mode is on.
+    pub fn is_whitelist_mode(&self) -> bool {
+        self.whitelist_mode
+    }
+
+    /// Allows `account_id` to transfer tokens while the whitelist mode is on.
+    /// Can only be called by the owner.
+    pub fn whitelist_add(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.whitelist.insert(&account_id) {
+            log!("Account @{} is whitelisted", account_id);
+        }
+    }
+
+    /// Removes `account_id` from the whitelist. Can only be called by the owner.
+    pub fn whitelist_remove(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.whitelist.remove(&account_id) {
+            log!("Account @{} is removed from the whitelist", account_id);
+        }
+    }
+
+    /// Returns whether `account_id` is whitelisted. The owner is always whitelisted.
+    pub fn is_whitelisted(&self, account_id: AccountId) -> bool {
+        account_id == self.owner_id || self.whitelist.contains(&account_id)
+    }
+
+    /// Allows `ft_transfer_call` to `account_id`. While the allowlist isn't empty, only the
+    /// accounts in it can receive `ft_transfer_call`. Can only be called by the owner.
+    pub fn transfer_call_allowlist_add(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.transfer_call_allowlist.insert(&account_id) {
+            log!("Account @{} can receive transfer calls", account_id);
+        }
+    }
+
+    /// Removes `account_id` from the transfer call allowlist. Can only be called by the owner.
+    pub fn transfer_call_allowlist_remove(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.transfer_call_allowlist.remove(&account_id) {
+            log!("Account @{} is removed from the transfer call allowlist", account_id);
+        }
+    }
+
+    /// Returns the accounts that can receive `ft_transfer_call`. Empty if any account can.
+    pub fn get_transfer_call_allowlist(&self) -> Vec<AccountId> {
+        self.transfer_call_allowlist.to_vec()
+    }
+
+    /// Replaces the token metadata. Can only be called by the owner.
+    /// `decimals` can't be changed, since that would change the meaning of all balances.
+    pub fn set_metadata(&mut self, metadata: FungibleTokenMetadata) {
+        self.assert_owner();
+        self.internal_set_metadata(metadata);
+        log!("Metadata set by @{}", self.owner_id);
+    }
+
+    /// Replaces the name and the symbol of the token. At least `rename_cooldown_ns` have to pass
+    /// between two renames. Can only be called by the owner.
+    pub fn rename(&mut self, name: String, symbol: String) {
+        self.assert_owner();
+        if let Some(last_rename_ts) = self.last_rename_ts {
+            assert!(
+                env::block_timestamp() - last_rename_ts >= self.rename_cooldown_ns,
+                "The token can be renamed again at {}",
+                last_rename_ts + self.rename_cooldown_ns
+            );
+        }
+        let mut metadata = self.metadata.get().unwrap();
+        metadata.name = name;
+        metadata.symbol = symbol;
+        self.internal_set_metadata(metadata);
+        self.last_rename_ts = Some(env::block_timestamp());
+        log!("Name and symbol set by @{}", self.owner_id);
+    }
+
+    /// Sets the minimum time between two renames. Can only be called by the owner.
+    pub fn set_rename_cooldown(&mut self, rename_cooldown_ns: U64) {
+        self.assert_owner();
+        log!("Rename cooldown set to {} ns by @{}", rename_cooldown_ns.0, self.owner_id);
+        self.rename_cooldown_ns = rename_cooldown_ns.into();
+    }
+
+    /// Returns the minimum time between two renames, in nanoseconds.
+    pub fn get_rename_cooldown(&self) -> U64 {
+        self.rename_cooldown_ns.into()
+    }
+
+    /// Sets the `version` of the logged `nep141` events, for indexers that expect a specific one.
+    /// Can only be called by the owner.
+    pub fn set_event_version(&mut self, version: String) {
+        self.assert_owner();
+        log!("Event version set to {} by @{}", version, self.owner_id);
+        self.event_version = version;
+    }
+
+    /// Returns the `version` of the logged `nep141` events.
+    pub fn get_event_version(&self) -> String {
+        self.event_version.clone()
+    }
+
+    /// Enables or disables the `nep141` events of transfers, mints and burns, which are on by
+    /// default. Disabling them saves the gas of logging on high-volume transfers, but indexers and
+    /// wallets that track balances from the events then miss those changes and have to query
+    /// `ft_balance_of` instead. Balances and the total supply are updated either way. Can only be
+    /// called by the owner.
+    pub fn set_emit_events(&mut self, enabled: bool) {
+        self.assert_owner();
+        self.emit_events = enabled;
+        log!("Events set to {} by @{}", enabled, self.owner_id);
+    }
+
+    /// Returns whether the `nep141` events of transfers, mints and burns are logged.
+    pub fn is_emitting_events(&self) -> bool {
+        self.emit_events
+    }
+
+    /// Sets the metadata shown for `locale`, e.g. "es". The metadata returned by `ft_metadata`
+    /// isn't affected, and `decimals` has to match it. Can only be called by the owner.
+    pub fn set_locale_metadata(&mut self, locale: String, metadata: FungibleTokenMetadata) {
+        self.assert_owner();
+        self.assert_metadata_not_locked();
+        metadata.assert_valid();
+        assert!(
+            metadata.decimals == self.metadata.get().unwrap().decimals,
+            "Decimals can't be changed"
+        );
+        self.extra_metadata.insert(&locale, &metadata);
+        log!("Metadata for {} set by @{}", locale, self.owner_id);
+    }
+
+    /// Returns the metadata set for `locale`, or `None` if the token isn't localized for it.
+    pub fn ft_metadata_locale(&self, locale: String) -> Option<FungibleTokenMetadata> {
+        self.extra_metadata.get(&locale)
+    }
+
+    /// Replaces the token icon. Can only be called by the owner.
+    pub fn set_icon(&mut self, icon: Option<String>) {
+        self.assert_owner();
+        let mut metadata = self.metadata.get().unwrap();
+        metadata.icon = icon;
+        self.internal_set_metadata(metadata);
+        log!("Icon set by @{}", self.owner_id);
+    }
+
+    /// Replaces the token reference and its hash. `reference_hash` is the SHA-256 hash of the
+    /// JSON file at `reference`, so it has to be 32 bytes long. Can only be called by the owner.
+    pub fn set_reference(
+        &mut self,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    ) {
+        self.assert_owner();
+        if let (Some(_), Some(reference_hash)) = (&reference, &reference_hash) {
+            assert!(
+                reference_hash.0.len() == 32,
+                "The reference hash has to be a 32 bytes SHA-256 hash, got {} bytes",
+                reference_hash.0.len()
+            );
+        }
+        log!("Reference set to {:?} by @{}", reference, self.owner_id);
+        let mut metadata = self.metadata.get().unwrap();
+        metadata.reference = reference;
+        metadata.reference_hash = reference_hash;
+        self.internal_set_metadata(metadata);
+    }
+
+    /// Locks the metadata permanently: it can't be replaced, renamed or localized anymore.
+    /// This can't be undone. Can only be called by the owner.
+    pub fn lock_metadata(&mut self) {
+        self.assert_owner();
+        self.metadata_locked = true;
+        log!("The metadata was locked by @{}", self.owner_id);
+    }
+
+    /// Returns whether the metadata is locked permanently.
+    pub fn is_metadata_locked(&self) -> bool {
+        self.metadata_locked
+    }
+
+    /// Allows `account_id` to mint tokens. Can only be called by the owner.
+    pub fn add_minter(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.minters.insert(&account_id) {
+            log!("Account @{} is granted the minter role", account_id);
+        }
+    }
+
+    /// Revokes the minter role from `account_id`. Can only be called by the owner.
+    pub fn remove_minter(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if self.minters.remove(&account_id) {
+            log!("Account @{} is revoked the minter role", account_id);
+        }
+    }
+
+    /// Returns whether `account_id` can mint tokens. The owner is always a minter.
+    pub fn is_minter(&self, account_id: AccountId) -> bool {
+        account_id == self.owner_id || self.minters.contains(&account_id)
+    }
+
+    fn internal_set_fee_basis_points(&mut self, fee_basis_points: u16) {
+        assert_valid_fee(fee_basis_points);
+        log!("Transfer fee set to {} basis points by @{}", fee_basis_points, self.owner_id);
+        self.fee_basis_points = fee_basis_points;
+    }
+
+    fn internal_set_owner(&mut self, new_owner: AccountId) {
+        OwnershipTransferred { old_owner: &self.owner_id, new_owner: &new_owner }.emit();
+        self.owner_id = new_owner;
+        self.pending_owner = None;
+    }
+
+    fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+
+    fn assert_minter(&self) {
+        require(self.is_minter(env::predecessor_account_id()), ContractError::NotMinter);
+    }
+
+    /// Wrapped NEAR is only minted and burned by `near_deposit` and `near_withdraw`, which keeps
+    /// it backed by the deposited NEAR.
+    fn assert_not_wrappable(&self) {
+        require(!self.wrappable, ContractError::MintingDisabled);
+    }
+
+    /// Checks that minting `amount` keeps the total supply within the max supply, counting the
+    /// part of the reward distribution in progress that isn't minted yet.
+    fn assert_within_max_supply(&self, amount: Balance) {
+        if let Some(max_supply) = self.max_supply {
+            let reserved = self
+                .reward_distribution
+                .as_ref()
+                .map_or(0, |distribution| distribution.total_reward.0 - distribution.distributed.0);
+            let headroom = max_supply.saturating_sub(self.token.total_supply);
+            require(amount <= headroom.saturating_sub(reserved), ContractError::MaxSupplyExceeded);
+        }
+    }
+
+    fn assert_before_mint_deadline(&self) {
+        if let Some(mint_deadline_ns) = self.mint_deadline_ns {
+            require(env::block_timestamp() <= mint_deadline_ns, ContractError::MintDeadlinePassed);
+        }
+    }
+
+    /// Returns the chain of hooks that validates every transfer.
+    /// See the `hook` module on how to add a custom hook.
+    fn transfer_hook(&self) -> impl TransferHook + '_ {
+        (
+            (
+                (
+                    (
+                        (
+                            PauseHook { paused: self.pause_flags.transfers },
+                            SnapshotHook { recording: self.is_snapshot_in_progress() },
+                        ),
+                        BlacklistHook { blacklist: &self.blacklist },
+                    ),
+                    FreezeHook { frozen_senders: &self.frozen_senders },
+                ),
+                WhitelistHook {
+                    enabled: self.whitelist_mode,
+                    whitelist: &self.whitelist,
+                    owner_id: &self.owner_id,
+                },
+            ),
+            MinAmountHook { min_amount: self.min_transfer_amount },
+        )
+    }
+
+    fn assert_transfer_allowed(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) {
+        self.transfer_hook().before_transfer(sender_id, receiver_id, amount);
+    }
+
+    fn assert_valid_memo(&self, memo: Option<&str>) {
+        if self.require_memo {
+            require(memo.is_some_and(|memo| !memo.is_empty()), ContractError::MemoRequired);
+        }
+        if let Some(memo) = memo {
+            require(
+                memo.len() <= self.max_memo_len as usize,
+                ContractError::MemoTooLong(self.max_memo_len),
+            );
+        }
+    }
+
+    fn assert_metadata_not_locked(&self) {
+        assert!(!self.metadata_locked, "The metadata is locked");
+    }
+
+    fn assert_valid_batch_len(&self, len: usize) {
+        require(
+            len <= self.max_batch_size as usize,
+            ContractError::BatchTooLong(self.max_batch_size),
+        );
+    }
+
+    /// Pauses the contract if `sender_id` moves more than the auto-pause threshold and returns a
+    /// call of `reject_auto_paused_transfer` if it did. The caller then has to drop the transfer
+    /// and return that call with `as_return`, or as its `PromiseOrValue`, which fails the
+    /// transaction. Panicking right away would revert the pause as well. The transfer hooks run
+    /// first, so a transfer that is
+    /// rejected anyway, e.g. from a frozen account, can't pause the contract. A sender without
+    /// the spendable balance can't trip the breaker either, so pausing the contract takes holding
+    /// the tokens.
+    fn trip_circuit_breaker(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Option<Promise> {
+        self.assert_transfer_allowed(sender_id, receiver_id, amount);
+        let threshold = self.circuit_breaker_threshold(sender_id, amount)?;
+        self.pause_flags = PauseFlags::ALL;
+        log!(
+            "WARNING: The transfer of {} exceeds the auto-pause threshold of {}, contract paused",
+            amount,
+            threshold
+        );
+        let args = near_sdk::serde_json::json!({ "threshold": U128(threshold) });
+        Some(Promise::new(env::current_account_id()).function_call(
+            "reject_auto_paused_transfer".to_string(),
+            args.to_string().into_bytes(),
+            0,
+            GAS_FOR_AUTO_PAUSE_REJECTION,
+        ))
+    }
+
+    /// Returns the auto-pause threshold if the transfer of `amount` from `sender_id` trips the
+    /// circuit breaker.
+    fn circuit_breaker_threshold(&self, sender_id: &AccountId, amount: Balance) -> Option<Balance> {
+        let threshold_bps = match self.auto_pause_threshold_bps {
+            Some(threshold_bps) if !self.pause_flags.transfers => threshold_bps,
+            _ => return None,
+        };
+        let threshold = basis_points_of(self.token.total_supply, threshold_bps);
+        if amount <= threshold || self.spendable_balance_of(sender_id.clone()).0 < amount {
+            return None;
+        }
+        Some(threshold)
+    }
+
+    /// Registers `account_id` and pays its minimum storage balance and the registration premium
+    /// from `deposit`. Returns the rest of the deposit.
+    fn internal_register_with_deposit(
+        &mut self,
+        account_id: &AccountId,
+        deposit: Balance,
+    ) -> Balance {
+        let min_balance = self.token.storage_balance_bounds().min.0;
+        require(deposit >= min_balance, ContractError::DepositBelowMinimum);
+        let premium = self.registration_premium;
+        require(deposit >= min_balance + premium, ContractError::PremiumNotCovered(premium));
+        let initial_storage_usage = env::storage_usage();
+        self.internal_register_account(account_id);
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        assert!(
+            storage_cost <= min_balance,
+            "The minimum storage balance doesn't cover the account storage"
+        );
+        self.internal_forward_premium(premium);
+        deposit - min_balance - premium
+    }
+
+    /// Logs `event` with the configured version, unless events are disabled.
+    pub(crate) fn emit_event(&self, event: Nep141Event) {
+        if self.emit_events {
+            event.emit(&self.event_version);
+        }
+    }
+
+    /// Sends the registration premium collected by a call to the treasury.
+    fn internal_forward_premium(&self, premium: Balance) {
+        if premium > 0 {
+            Promise::new(self.treasury_id.clone()).transfer(premium);
+        }
+    }
+
+    /// Checks that `account_id` can spend `amount` without touching its locked tokens.
+    fn assert_spendable(&self, account_id: &AccountId, amount: Balance) {
+        self.check_spendable(account_id, amount).unwrap_or_else(|error| error.panic())
+    }
+
+    fn check_spendable(
+        &self,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), ContractError> {
+        let balance = self.token.accounts.get(account_id).unwrap_or(0);
+        ensure(balance >= amount, ContractError::InsufficientBalance)?;
+        ensure(
+            balance - amount
+                >= self.internal_locked_balance_of(account_id)
+                    + self.internal_pending_balance_of(account_id),
+            ContractError::LockedBalance,
+        )
+    }
+
+    /// Returns the transfer fee and the burned amount of a transfer of `amount`.
+    fn transfer_deductions(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> (Balance, Balance) {
+        let fee = if self.is_fee_recipient(sender_id) || self.is_fee_recipient(receiver_id) {
+            0
+        } else {
+            self.deduction_of(amount, self.fee_basis_points)
+        };
+        let burn = self.deduction_of(amount, self.burn_basis_points);
+        (fee, burn)
+    }
+
+    /// Returns what a transfer of `amount` takes from the balance of `sender_id`: the amount, and
+    /// the fee and the burned part, which are charged on top of it.
+    fn transfer_debit(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Result<Balance, ContractError> {
+        let (fee, burn) = self.transfer_deductions(sender_id, receiver_id, amount);
+        amount
+            .checked_add(fee)
+            .and_then(|debit| debit.checked_add(burn))
+            .ok_or(ContractError::AmountOverflow)
+    }
+
+    /// Returns whether `account_id` receives transfer fees, so its own transfers aren't charged.
+    fn is_fee_recipient(&self, account_id: &AccountId) -> bool {
+        account_id == &self.treasury_id
+            || self.fee_recipients.iter().any(|(recipient_id, _)| recipient_id == account_id)
+    }
+
+    /// Splits `fee` between the fee recipients, or gives all of it to the treasury if there are
+    /// none. Empty shares are left out.
+    fn fee_shares(&self, fee: Balance) -> Vec<(AccountId, Balance)> {
+        let mut shares: Vec<(AccountId, Balance)> = if self.fee_recipients.is_empty() {
+            vec![(self.treasury_id.clone(), fee)]
+        } else {
+            self.fee_recipients
+                .iter()
+                .map(|(account_id, weight)| (account_id.clone(), basis_points_of(fee, *weight)))
+                .collect()
+        };
+        let distributed: Balance = shares.iter().map(|(_, share)| share).sum();
+        shares[0].1 += fee - distributed;
+        shares.retain(|(_, share)| *share > 0);
+        shares
+    }
+
+    /// Returns `basis_points` of `amount`, rounded as configured by `fee_round_up`.
+    fn deduction_of(&self, amount: Balance, basis_points: u16) -> Balance {
+        let deduction = basis_points_of(amount, basis_points);
+        let remainder = amount % 10_000 * Balance::from(basis_points) % 10_000;
+        if self.fee_round_up && remainder > 0 {
+            deduction + 1
+        } else {
+            deduction
+        }
+    }
+
+    /// Transfers `amount` from `sender_id` to `receiver_id`, sending the transfer fee to the
+    /// treasury and burning the burned part. Both are charged to the sender on top of `amount`,
+    /// which the receiver gets in full. Both are rounded down unless `fee_round_up` is set.
+    /// Transfers from or to the treasury are not charged a fee. Returns the fee and the burned
+    /// amount.
+    fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> (Balance, Balance) {
+        self.assert_transfer_allowed(sender_id, receiver_id, amount);
+        self.assert_valid_memo(memo.as_deref());
+        let debit = self
+            .transfer_debit(sender_id, receiver_id, amount)
+            .unwrap_or_else(|error| error.panic());
+        self.assert_spendable(sender_id, debit);
+        self.internal_record_daily_transfer(sender_id, amount);
+        self.internal_record_transfer_time(sender_id);
+        self.internal_count_transfer(sender_id);
+        self.internal_deduct_and_move(sender_id, receiver_id, amount, memo.as_deref())
+    }
+
+    /// Moves `amount` from `sender_id` to `receiver_id` and charges the transfer fee and the burned
+    /// part on top of it, without the checks of `internal_transfer`. Returns the fee and the burned
+    /// amount.
+    fn internal_deduct_and_move(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) -> (Balance, Balance) {
+        let (fee, burn) = self.transfer_deductions(sender_id, receiver_id, amount);
+        if fee == 0 && burn == 0 {
+            self.internal_move_tokens(sender_id, receiver_id, amount, memo);
+            return (0, 0);
+        }
+
+        require(sender_id != receiver_id, ContractError::SameAccount);
+        let fee_shares: Vec<(AccountId, U128)> = self
+            .fee_shares(fee)
+            .into_iter()
+            .map(|(account_id, share)| (account_id, U128(share)))
+            .collect();
+        let debit = self
+            .transfer_debit(sender_id, receiver_id, amount)
+            .unwrap_or_else(|error| error.panic());
+        // Unless there's a dead account, the burned part is withdrawn but not deposited anywhere,
+        // which reduces the total supply.
+        self.token.internal_withdraw(sender_id, debit);
+        self.token.internal_deposit(receiver_id, amount);
+        let amount = U128(amount);
+        let mut transfers = vec![FtTransfer {
+            old_owner_id: sender_id,
+            new_owner_id: receiver_id,
+            amount: &amount,
+            memo,
+        }];
+        for (recipient_id, share) in fee_shares.iter() {
+            self.token.internal_deposit(recipient_id, share.0);
+            transfers.push(FtTransfer {
+                old_owner_id: sender_id,
+                new_owner_id: recipient_id,
+                amount: share,
+                memo: Some("fee"),
+            });
+        }
+        let dead_id = self.dead_account().filter(|_| burn > 0);
+        let burned = U128(burn);
+        if let Some(dead_id) = &dead_id {
+            self.token.internal_deposit(dead_id, burn);
+            transfers.push(FtTransfer {
+                old_owner_id: sender_id,
+                new_owner_id: dead_id,
+                amount: &burned,
+                memo: Some("burn"),
+            });
+        }
+        self.emit_event(Nep141Event::FtTransfer(&transfers));
+        if burn > 0 && dead_id.is_none() {
+            self.emit_event(Nep141Event::FtBurn(&[FtBurn {
+                owner_id: sender_id,
+                amount: &U128(burn),
+                memo: Some("burn"),
+            }]));
+            self.on_tokens_burned(sender_id.clone(), burn);
+        }
+        (fee, burn)
+    }
+
+    /// Moves `amount` from `sender_id` to `receiver_id` without any of the checks of
+    /// `internal_transfer`, e.g. for the transfers of the owner. Balances are still frozen while
+    /// a snapshot is recorded.
+    fn internal_move_tokens(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        require(sender_id != receiver_id, ContractError::SameAccount);
+        require(amount > 0, ContractError::ZeroAmount);
+        self.assert_no_snapshot_in_progress();
+        self.token.internal_withdraw(sender_id, amount);
+        self.token.internal_deposit(receiver_id, amount);
+        self.emit_event(Nep141Event::FtTransfer(&[FtTransfer {
+            old_owner_id: sender_id,
+            new_owner_id: receiver_id,
+            amount: &U128(amount),
+            memo,
+        }]));
+    }
+
+    fn internal_set_metadata(&mut self, metadata: FungibleTokenMetadata) {
+        self.assert_metadata_not_locked();
+        metadata.assert_valid();
+        assert!(
+            metadata.decimals == self.metadata.get().unwrap().decimals,
+            "Decimals can't be changed"
+        );
+        self.metadata.set(&metadata);
+    }
+
+    /// Adds the storage of the entries kept for every account, in the holders set and the daily
+    /// transfer counters, to the storage paid on registration.
+    fn measure_account_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id: AccountId = "a".repeat(64).parse().unwrap();
+        self.holders.insert(&tmp_account_id);
+        self.daily_transfers.insert(&tmp_account_id, &(u64::MAX, Balance::MAX));
+        self.last_transfers.insert(&tmp_account_id, &u64::MAX);
+        self.transfer_counts.insert(&tmp_account_id, &u64::MAX);
+        self.token.account_storage_usage += env::storage_usage() - initial_storage_usage;
+        self.holders.remove(&tmp_account_id);
+        self.daily_transfers.remove(&tmp_account_id);
+        self.last_transfers.remove(&tmp_account_id);
+        self.transfer_counts.remove(&tmp_account_id);
+    }
+
+    fn internal_register_account(&mut self, account_id: &AccountId) {
+        self.token.internal_register_account(account_id);
+        self.on_account_registered(account_id);
+    }
+
+    fn on_account_registered(&mut self, account_id: &AccountId) {
+        self.holders.insert(account_id);
+        self.holders_count += 1;
+    }
+
+    /// Called after `account_id` is unregistered. The remaining `balance` has already been
+    /// subtracted from the total supply by the storage management, so it's reported as burned.
+    /// The closure is recorded until the owner purges it.
+    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        if self.holders.remove(&account_id) {
+            self.holders_count = self.holders_count.saturating_sub(1);
+        }
+        self.legacy_accounts.remove(&account_id);
+        self.daily_transfers.remove(&account_id);
+        self.last_transfers.remove(&account_id);
+        self.transfer_counts.remove(&account_id);
+        self.internal_remove_signing_key(&account_id);
+        log!("Closed @{} with {}", account_id, balance);
+        if balance > 0 {
+            self.total_burned += balance;
+            self.emit_event(Nep141Event::FtBurn(&[FtBurn {
+                owner_id: &account_id,
+                amount: &U128(balance),
+                memo: Some("Account closed"),
+            }]));
+            self.internal_record_supply();
+        }
+    }
+
+    /// Checks that the balances of all holders add up to the total supply and cover the locked
+    /// tokens. Closed accounts are burned, so they don't count towards either.
+    #[cfg(test)]
+    fn debug_assert_supply_consistent(&self) {
+        let balances: Balance = self
+            .holders
+            .iter()
+            .map(|account_id| self.token.accounts.get(&account_id).unwrap_or(0))
+            .sum();
+        assert_eq!(balances, self.token.total_supply, "The balances don't add up to the supply");
+        assert_eq!(
+            self.total_minted - self.total_burned,
+            self.token.total_supply,
+            "The minted and burned tokens don't add up to the supply"
+        );
+        assert!(self.locked_total <= balances, "The locked tokens exceed the balances");
+        assert_eq!(self.holders.len(), self.holders_count, "The holders count is out of sync");
+    }
+
+    /// Counts `amount` against the daily limit of `account_id`, starting a new count every day.
+    fn internal_record_daily_transfer(&mut self, account_id: &AccountId, amount: Balance) {
+        let record = self.daily_record_after(account_id, amount).unwrap_or_else(|e| e.panic());
+        if let Some(record) = record {
+            self.daily_transfers.insert(account_id, &record);
+        }
+    }
+
+    /// Returns the daily transfer count of `account_id` after a transfer of `amount`, or `None`
+    /// if there's no daily limit.
+    fn daily_record_after(
+        &self,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Result<Option<(u64, Balance)>, ContractError> {
+        let daily_limit = match self.daily_limit {
+            Some(daily_limit) => daily_limit,
+            None => return Ok(None),
+        };
+        let day = env::block_timestamp() / DAY_NS;
+        let moved_today = match self.daily_transfers.get(account_id) {
+            Some((last_day, moved)) if last_day == day => moved,
+            _ => 0,
+        };
+        let moved_today = moved_today.saturating_add(amount);
+        ensure(moved_today <= daily_limit, ContractError::DailyLimitExceeded)?;
+        Ok(Some((day, moved_today)))
+    }
+
+    /// Records the time of a transfer of `account_id`, which has to be past its cooldown.
+    fn internal_record_transfer_time(&mut self, account_id: &AccountId) {
+        if self.transfer_cooldown_ns == 0 || account_id == &self.owner_id {
+            return;
+        }
+        self.check_cooldown(account_id).unwrap_or_else(|error| error.panic());
+        self.last_transfers.insert(account_id, &env::block_timestamp());
+    }
+
+    /// Counts a transfer sent by `account_id`. A batch counts as one transfer.
+    fn internal_count_transfer(&mut self, account_id: &AccountId) {
+        let count = self.transfer_counts.get(account_id).unwrap_or(0);
+        self.transfer_counts.insert(account_id, &(count + 1));
+    }
+
+    /// Checks that the cooldown since the last transfer of `account_id` has elapsed.
+    fn check_cooldown(&self, account_id: &AccountId) -> Result<(), ContractError> {
+        if self.transfer_cooldown_ns == 0 || account_id == &self.owner_id {
+            return Ok(());
+        }
+        match self.last_transfers.get(account_id) {
+            Some(last) if env::block_timestamp() - last < self.transfer_cooldown_ns => {
+                Err(ContractError::TransferCooldown(
+                    last + self.transfer_cooldown_ns - env::block_timestamp(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts `amount` against the mint cap of the current epoch, starting a new count when the
+    /// epoch rolls over.
+    fn internal_record_mint(&mut self, amount: Balance) {
+        if self.epoch_length_ns == 0 {
+            return;
+        }
+        let epoch = env::block_timestamp() / self.epoch_length_ns;
+        if epoch != self.current_epoch {
+            self.current_epoch = epoch;
+            self.minted_this_epoch = 0;
+        }
+        let minted_this_epoch = self.minted_this_epoch.saturating_add(amount);
+        require(minted_this_epoch <= self.mint_cap_per_epoch, ContractError::MintCapExceeded);
+        self.minted_this_epoch = minted_this_epoch;
+    }
+
+    /// Calls `on_supply_change` on the registry, if it's set. The call has no callback, so its
+    /// failure doesn't affect the mint or burn that triggered it.
+    fn notify_supply_change(&self) {
+        if let Some(registry_id) = self.registry_id.clone() {
+            let args =
+                near_sdk::serde_json::json!({ "new_total_supply": U128(self.token.total_supply) });
+            Promise::new(registry_id).function_call(
+                "on_supply_change".to_string(),
+                args.to_string().into_bytes(),
+                0,
+                GAS_FOR_SUPPLY_CHANGE,
+            );
+        }
+    }
+
+    fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
+        log!("Account @{} burned {}", account_id, amount);
+        self.total_burned += amount;
+        self.internal_record_supply();
+    }
+}
+
+/// Charges the predecessor for the storage used since `initial_storage_usage` out of the attached
+/// deposit and refunds the rest, including the cost of any released storage.
+fn settle_storage_deposit(initial_storage_usage: StorageUsage) {
+    let attached_deposit = env::attached_deposit();
+    let storage_usage = env::storage_usage();
+    let refund = if storage_usage >= initial_storage_usage {
+        let required_cost =
+            Balance::from(storage_usage - initial_storage_usage) * env::storage_byte_cost();
+        require(
+            required_cost <= attached_deposit,
+            ContractError::InsufficientStorage(required_cost),
+        );
+        attached_deposit - required_cost
+    } else {
+        attached_deposit
+            + Balance::from(initial_storage_usage - storage_usage) * env::storage_byte_cost()
+    };
+    if refund > 0 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
+}
+
+/// Formats `amount` as a fixed-point number with `decimals` fractional digits, without trailing
+/// zeros.
+fn format_amount(amount: Balance, decimals: u8) -> String {
+    let unit = 10u128.pow(u32::from(decimals));
+    let whole = amount / unit;
+    let fraction = amount % unit;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = usize::from(decimals));
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+/// Zeroes the fractional digits of `amount` beyond `keep_decimals`, `amount` having `decimals`
+/// fractional digits.
+fn round_down(amount: Balance, decimals: u8, keep_decimals: u8) -> Balance {
+    if keep_decimals >= decimals {
+        return amount;
+    }
+    let step = 10u128.pow(u32::from(decimals - keep_decimals));
+    amount - amount % step
+}
+
+fn assert_valid_decimals(decimals: u8, expected_decimals: Option<u8>) {
+    assert!(decimals <= MAX_DECIMALS, "The decimals can't exceed {}", MAX_DECIMALS);
+    if let Some(expected_decimals) = expected_decimals {
+        assert_eq!(
+            decimals, expected_decimals,
+            "The decimals don't match the expected {}",
+            expected_decimals
+        );
+    }
+}
+
+fn assert_valid_burn(burn_basis_points: u16) {
+    assert!(
+        burn_basis_points <= MAX_BURN_BASIS_POINTS,
+        "The burn can't exceed {} basis points",
+        MAX_BURN_BASIS_POINTS
+    );
+}
+
+/// Returns `basis_points` of `amount`, rounded down. Fits in 128 bits for any amount, as long as
+/// `basis_points` is at most 10000.
+fn basis_points_of(amount: Balance, basis_points: u16) -> Balance {
+    mul_div(amount, Balance::from(basis_points), 10_000)
+}
+
+fn assert_valid_fee(fee_basis_points: u16) {
+    assert!(
+        fee_basis_points <= MAX_FEE_BASIS_POINTS,
+        "The fee can't exceed {} basis points",
+        MAX_FEE_BASIS_POINTS
+    );
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        // Checked explicitly so the message doesn't depend on the SDK version.
+        require(env::attached_deposit() == 1, ContractError::OneYocto);
+        let sender_id = env::predecessor_account_id();
+        if let Some(rejection) = self.trip_circuit_breaker(&sender_id, &receiver_id, amount.into())
+        {
+            rejection.as_return();
+            return;
+        }
+        if self.fee_token_id.is_some() {
+            self.internal_transfer_after_fee(sender_id, receiver_id, amount, memo);
+            return;
+        }
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        if let Some(rejection) = self.trip_circuit_breaker(&sender_id, &receiver_id, amount.into())
+        {
+            return PromiseOrValue::Promise(rejection);
+        }
+        assert!(
+            self.transfer_call_allowlist.is_empty()
+                || self.transfer_call_allowlist.contains(&receiver_id),
+            "The account {} can't receive transfer calls",
+            receiver_id
+        );
+        self.assert_valid_memo(memo.as_deref());
+        let debit = self
+            .transfer_debit(&sender_id, &receiver_id, amount.into())
+            .unwrap_or_else(|error| error.panic());
+        self.assert_spendable(&sender_id, debit);
+        self.internal_record_daily_transfer(&sender_id, amount.into());
+        self.internal_record_transfer_time(&sender_id);
+        self.internal_count_transfer(&sender_id);
+        // Same as the standard `ft_transfer_call`, but the transfer event has the configured
+        // version and `ft_on_transfer` gets the configured gas. The fee and the burned part are
+        // charged on top of the amount up front and aren't refunded: the receiver is offered the
+        // whole amount, and only the unused part of it goes back to the sender.
+        let receiver_gas = match self.ft_on_transfer_gas {
+            Some(gas) => {
+                assert!(
+                    env::prepaid_gas() >= gas + GAS_FOR_FT_TRANSFER_CALL,
+                    "More gas is required"
+                );
+                gas
+            }
+            None => {
+                assert!(env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL, "More gas is required");
+                env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL
+            }
+        };
+        self.internal_deduct_and_move(&sender_id, &receiver_id, amount.into(), memo.as_deref());
+        let on_transfer_args = near_sdk::serde_json::json!({
+            "sender_id": sender_id,
+            "amount": amount,
+            "msg": route_transfer_msg(msg),
+        });
+        let resolve_args = near_sdk::serde_json::json!({
+            "sender_id": sender_id,
+            "receiver_id": receiver_id,
+            "amount": amount,
+        });
+        Promise::new(receiver_id)
+            .function_call(
+                "ft_on_transfer".to_string(),
+                on_transfer_args.to_string().into_bytes(),
+                0,
+                receiver_gas,
+            )
+            .then(Promise::new(env::current_account_id()).function_call(
+                "ft_resolve_transfer".to_string(),
+                resolve_args.to_string().into_bytes(),
+                0,
+                GAS_FOR_RESOLVE_TRANSFER,
+            ))
+            .into()
+    }
+
+    /// Reads the total supply that is maintained by every mint and burn, without iterating the
+    /// accounts.
+    fn ft_total_supply(&self) -> U128 {
+        self.token.total_supply.into()
+    }
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        self.token.ft_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    /// Same as the standard resolver, but emits an `ft_transfer` event for the tokens refunded to
+    /// the sender, or an `ft_burn` event if the sender's account was closed in the meantime.
+    #[private]
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        let amount: Balance = amount.into();
+        // Get the unused amount from the `ft_on_transfer` call result.
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                if let Ok(unused_amount) = near_sdk::serde_json::from_slice::<U128>(&value) {
+                    std::cmp::min(amount, unused_amount.0)
+                } else {
+                    amount
+                }
+            }
+            PromiseResult::Failed => amount,
+        };
+        if unused_amount == 0 {
+            return amount.into();
+        }
+        let receiver_balance = self.token.accounts.get(&receiver_id).unwrap_or(0);
+        if receiver_balance == 0 {
+            return amount.into();
+        }
+        let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+        // The refund can't wait for the snapshot, so it records the balances from before it.
+        self.internal_checkpoint_balance(&receiver_id);
+        self.internal_checkpoint_balance(&sender_id);
+        self.token.accounts.insert(&receiver_id, &(receiver_balance - refund_amount));
+        if let Some(sender_balance) = self.token.accounts.get(&sender_id) {
+            let new_sender_balance = sender_balance
+                .checked_add(refund_amount)
+                .unwrap_or_else(|| env::panic_str("Balance overflow"));
+            self.token.accounts.insert(&sender_id, &new_sender_balance);
+            self.emit_event(Nep141Event::FtTransfer(&[FtTransfer {
+                old_owner_id: &receiver_id,
+                new_owner_id: &sender_id,
+                amount: &refund_amount.into(),
+                memo: Some("refund"),
+            }]));
+            (amount - refund_amount).into()
+        } else {
+            // The sender's account was closed, so the refunded tokens are burned.
+            self.token.total_supply -= refund_amount;
+            self.emit_event(Nep141Event::FtBurn(&[FtBurn {
+                owner_id: &receiver_id,
+                amount: &refund_amount.into(),
+                memo: Some("refund"),
+            }]));
+            self.on_tokens_burned(sender_id, refund_amount);
+            amount.into()
+        }
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    /// Registers `account_id`, or the predecessor if it's not set. The storage usage is measured
+    /// before and after the registration, and the minimum storage balance is charged, which is
+    /// what `storage_unregister` refunds and always covers the measured increase. The
+    /// registration premium is charged as well and forwarded to the treasury. The rest of the
+    /// attached deposit is refunded to the predecessor, all of it if the account is already
+    /// registered. The balance bounds are fixed, so `registration_only` makes no difference.
+    #[payable]
+    #[allow(unused_variables)]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let refund = if self.token.accounts.contains_key(&account_id) {
+            log!("The account is already registered, refunding the deposit");
+            amount
+        } else {
+            self.internal_register_with_deposit(&account_id, amount)
+        };
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.token.storage_withdraw(amount)
+    }
+
+    /// Closes the predecessor's account and refunds its storage balance, less the storage of the
+    /// record kept for `closed_account_info`. A positive balance is burned if `force` is set.
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        let account_id = env::predecessor_account_id();
+        self.assert_no_snapshot_in_progress();
+        assert!(
+            self.reward_distribution.is_none(),
+            "The account can't be unregistered while rewards are distributed"
+        );
+        assert!(
+            self.internal_pending_balance_of(&account_id) == 0,
+            "The account can't be unregistered while it has pending transfers"
+        );
+        assert!(
+            self.locks.get(&account_id).is_none(),
+            "The account can't be unregistered while it has a lock"
+        );
+        // Accounts registered before the migration paid the old storage minimum, which is all
+        // they get back, less the storage of the closure record that stays in the state. The
+        // record can take more than the old minimum, which leaves nothing to refund.
+        let account_storage_usage = self.token.account_storage_usage;
+        let mut refunded_storage_usage =
+            self.legacy_storage_usage(&account_id).unwrap_or(account_storage_usage);
+        if let Some(balance) = self.token.accounts.get(&account_id) {
+            let record_storage_usage = self.internal_record_closed_account(&account_id, balance);
+            refunded_storage_usage = refunded_storage_usage.saturating_sub(record_storage_usage);
+        }
+        self.token.account_storage_usage = refunded_storage_usage;
+        let closed = self.token.internal_storage_unregister(force);
+        self.token.account_storage_usage = account_storage_usage;
+        if let Some((account_id, balance)) = closed {
+            self.on_account_closed(account_id, balance);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.token.storage_balance_bounds()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        let storage_balance = self.token.storage_balance_of(account_id.clone())?;
+        match self.legacy_storage_usage(&account_id) {
+            Some(legacy_storage_usage) => Some(StorageBalance {
+                total: (Balance::from(legacy_storage_usage) * env::storage_byte_cost()).into(),
+                available: 0.into(),
+            }),
+            None => Some(storage_balance),
+        }
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::MockedBlockchain;
+    use near_sdk::{testing_env, Balance};
+
+    use super::*;
+    use crate::simulate::{TransferPreview, TransferReceipt};
+
+    const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+
+    fn test_metadata() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Test token".to_string(),
+            symbol: "TEST".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 6,
+        }
+    }
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    #[test]
+    fn test_new() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract =
+            Contract::new_default_meta(accounts(1).into(), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.is_view(true).build());
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_holders_count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract is not initialized")]
+    fn test_default() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let _contract = Contract::default();
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        let transfer_amount = TOTAL_SUPPLY / 3;
+        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .account_balance(env::account_balance())
+            .is_view(true)
+            .attached_deposit(0)
+            .build());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - transfer_amount));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_new_with_expected_decimals() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            test_metadata(),
+            Some(InitConfig { expected_decimals: Some(6), ..Default::default() }),
+        );
+        testing_env!(context.is_view(true).build());
+        assert_eq!(contract.ft_metadata().decimals, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "The decimals can't exceed 24")]
+    fn test_new_with_too_many_decimals() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let metadata = FungibleTokenMetadata { decimals: 30, ..test_metadata() };
+        Contract::new(accounts(2), TOTAL_SUPPLY.into(), metadata, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The total supply can't exceed the max supply")]
+    fn test_new_with_supply_above_max_supply() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            test_metadata(),
+            Some(InitConfig { max_supply: Some((TOTAL_SUPPLY - 1).into()), ..Default::default() }),
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The total supply should be a positive number unless allow_zero_supply is set"
+    )]
+    fn test_new_with_zero_supply() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        Contract::new(accounts(2), 0.into(), test_metadata(), None);
+    }
+
+    #[test]
+    fn test_new_with_allowed_zero_supply() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new(
+            accounts(2),
+            0.into(),
+            test_metadata(),
+            Some(InitConfig {
+                max_supply: Some(TOTAL_SUPPLY.into()),
+                allow_zero_supply: true,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(contract.ft_total_supply().0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The decimals don't match the expected 18")]
+    fn test_new_with_unexpected_decimals() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            test_metadata(),
+            Some(InitConfig { expected_decimals: Some(18), ..Default::default() }),
+        );
+    }
+
+    #[test]
+    fn test_mint() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .build());
+        contract.ft_mint(accounts(1), 1_000.into(), None);
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(0).build());
+        contract.ft_mint(accounts(1), 500.into(), Some("second batch".to_string()));
+
+        testing_env!(context.is_view(true).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_500);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_500);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_mint_whole() {
+        let (_, mut contract) = setup_registered_receiver();
+        contract.ft_mint_whole(accounts(1), 5.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 5_000_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 5_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_OVERFLOW")]
+    fn test_mint_whole_overflow() {
+        let (_, mut contract) = setup_registered_receiver();
+        contract.ft_mint_whole(accounts(1), (u128::MAX / 1_000_000 + 1).into(), None);
+    }
+
+    #[test]
+    fn test_mint_registers_new_account() {
+        let (mut context, mut contract) = setup_with_fee(0);
+        contract.set_registration_premium(1_000.into());
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance + 1_500)
+            .build());
+        contract.ft_mint(accounts(4), 1_000.into(), None);
+        assert!(contract.is_registered(accounts(4)));
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 1_000);
+        assert_eq!(contract.storage_balance_of(accounts(4)).unwrap().total.0, min_balance);
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, accounts(3));
+        assert!(format!("{:?}", receipts[0].actions).contains("deposit: 1000 "));
+        assert_eq!(receipts[1].receiver_id, accounts(2));
+        assert!(format!("{:?}", receipts[1].actions).contains("deposit: 500 "));
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_mint_notifies_registry() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.set_registry(Some(accounts(4)));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(0).build());
+        contract.ft_mint(accounts(2), 1_000.into(), None);
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(4));
+        assert!(format!("{:?}", receipts[0].actions).contains("on_supply_change"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_MINTER: Only the owner or a minter can call this method")]
+    fn test_mint_not_owner() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_mint(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR_INSUFFICIENT_STORAGE: The attached deposit is less than the minimum storage balance"
+    )]
+    fn test_mint_unregistered_without_deposit() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(0).build());
+        contract.ft_mint(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_mint_batch_airdrop() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let recipients: Vec<AccountId> =
+            (0..50).map(|i| format!("user{}.near", i).parse().unwrap()).collect();
+        let amounts: Vec<U128> = (1..=50).map(|i| U128(i * 10)).collect();
+        let storage_cost = contract.storage_balance_bounds().min.0 * 50;
+        let initial_storage_usage = env::storage_usage();
+        testing_env!(context
+            .storage_usage(initial_storage_usage)
+            .attached_deposit(storage_cost + 1)
+            .build());
+        contract.ft_mint_batch(recipients.clone(), amounts);
+
+        let storage_increase = Balance::from(env::storage_usage() - initial_storage_usage);
+        assert!(storage_increase * env::storage_byte_cost() <= storage_cost);
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(recipients[0].clone()).0, 10);
+        assert_eq!(contract.ft_balance_of(recipients[49].clone()).0, 500);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 12_750);
+        assert_eq!(contract.ft_holders_count(), 51);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_STORAGE: Must attach")]
+    fn test_mint_batch_without_storage_deposit() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(0).build());
+        contract.ft_mint_batch(vec![accounts(1), accounts(2)], vec![U128(1), U128(2)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BATCH_TOO_LONG: A batch can have at most 100 recipients")]
+    fn test_mint_batch_too_long() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.ft_mint_batch(vec![accounts(2); 101], vec![U128(1); 101]);
+    }
+
+    #[test]
+    fn test_mint_batch_at_max_size() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(0).build());
+        contract.set_max_batch_size(2);
+        assert_eq!(contract.get_max_batch_size(), 2);
+        contract.ft_mint_batch(vec![accounts(1), accounts(2)], vec![U128(1), U128(2)]);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+    }
+
+    #[test]
+    fn test_transfer_batch_at_max_size() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        register_account(&mut context, &mut contract, accounts(3));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_max_batch_size(2);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(3)],
+            vec![100.into(), 200.into()],
+            None,
+        );
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BATCH_TOO_LONG: A batch can have at most 2 recipients")]
+    fn test_transfer_batch_over_max_size() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        register_account(&mut context, &mut contract, accounts(3));
+        register_account(&mut context, &mut contract, accounts(4));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_max_batch_size(2);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(3), accounts(4)],
+            vec![100.into(), 200.into(), 300.into()],
+            None,
+        );
+    }
+
+    #[test]
+    fn test_burn() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_burn(1_000.into(), Some("burn".to_string()));
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1_000);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_total_minted_and_burned() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert_eq!(contract.total_minted().0, TOTAL_SUPPLY);
+        assert_eq!(contract.total_burned().0, 0);
+
+        contract.ft_mint(accounts(2), 5_000.into(), None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_burn(1_000.into(), None);
+
+        assert_eq!(contract.total_minted().0, TOTAL_SUPPLY + 5_000);
+        assert_eq!(contract.total_burned().0, 1_000);
+        assert_eq!(
+            contract.total_minted().0 - contract.total_burned().0,
+            contract.ft_total_supply().0
+        );
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_BALANCE: The account doesn't have enough balance")]
+    fn test_burn_more_than_balance() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_burn((TOTAL_SUPPLY + 1).into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_burn_without_deposit() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(0).build());
+        contract.ft_burn(1_000.into(), None);
+    }
+
+    #[test]
+    fn test_set_owner() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert_eq!(contract.get_owner(), accounts(2));
+
+        testing_env!(context.attached_deposit(1).build());
+        let index = contract.set_owner(accounts(3));
+        assert_eq!(contract.get_owner(), accounts(2));
+        testing_env!(context
+            .attached_deposit(0)
+            .block_timestamp(crate::timelock::MIN_ACTION_DELAY_NS)
+            .build());
+        contract.execute_action(index);
+        assert_eq!(contract.get_owner(), accounts(3));
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![
+                "Executing admin action #0",
+                concat!(
+                    r#"EVENT_JSON:{"standard":"ft_admin","version":"1.0.0","event":"ownership_transferred","#,
+                    r#""data":[{"old_owner":"charlie","new_owner":"danny"}]}"#
+                )
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_set_owner_by_stranger() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.set_owner(accounts(1));
+    }
+
+    #[test]
+    fn test_two_step_ownership_transfer() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.propose_owner(accounts(1));
+        // Proposing again replaces the pending owner.
+        contract.propose_owner(accounts(3));
+        assert_eq!(contract.get_pending_owner(), Some(accounts(3)));
+        assert_eq!(contract.get_owner(), accounts(2));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.accept_owner();
+        assert_eq!(contract.get_owner(), accounts(3));
+        assert_eq!(contract.get_pending_owner(), None);
+        let logs = near_sdk::test_utils::get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: near_sdk::serde_json::Value =
+            near_sdk::serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(
+            event,
+            near_sdk::serde_json::json!({
+                "standard": "ft_admin",
+                "version": "1.0.0",
+                "event": "ownership_transferred",
+                "data": [{ "old_owner": accounts(2), "new_owner": accounts(3) }],
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept the ownership")]
+    fn test_accept_owner_not_pending_owner() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.propose_owner(accounts(1));
+        contract.propose_owner(accounts(3));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_owner();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept the ownership")]
+    fn test_accept_cancelled_ownership_transfer() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.propose_owner(accounts(1));
+        contract.cancel_ownership_transfer();
+        assert_eq!(contract.get_pending_owner(), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_owner();
+    }
+
+    fn register_account(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        account_id: AccountId,
+    ) {
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(account_id)
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
+    fn setup_registered_receiver() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        register_account(&mut context, &mut contract, accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(2))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_resolve_transfer_refund_emits_event() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"300\"".to_vec())]
+        );
+        let used_amount = contract.ft_resolve_transfer(accounts(2), accounts(1), 1_000.into());
+        assert_eq!(used_amount.0, 700);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 700);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 700);
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{{"old_owner_id":"{}","new_owner_id":"{}","amount":"300","memo":"refund"}}]}}"#,
+                accounts(1),
+                accounts(2)
+            )]
+        );
+        contract.debug_assert_supply_consistent();
+    }
+
+    fn setup_paused_transfer() -> (VMContextBuilder, Contract) {
+        let (context, mut contract) = setup_registered_receiver();
+        contract.pause();
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED: Contract is paused")]
+    fn test_transfer_paused() {
+        let (mut context, mut contract) = setup_paused_transfer();
+        assert!(contract.is_paused());
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_after_unpause() {
+        let (mut context, mut contract) = setup_paused_transfer();
+        testing_env!(context.is_view(true).build());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY);
+
+        testing_env!(context.is_view(false).build());
+        contract.unpause();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_pause_by_stranger() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.pause();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED: Sender is blacklisted")]
+    fn test_transfer_blacklisted_sender() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.blacklist_add(accounts(2));
+        assert!(contract.is_blacklisted(accounts(2)));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED: Receiver is blacklisted")]
+    fn test_transfer_blacklisted_receiver() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.blacklist_add(accounts(1));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_frozen_account_can_receive() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.freeze(accounts(1));
+        assert!(contract.is_frozen(accounts(1)));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+
+        testing_env!(context.attached_deposit(0).build());
+        contract.unfreeze(accounts(1));
+        assert!(!contract.is_frozen(accounts(1)));
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FROZEN: Sender is frozen")]
+    fn test_frozen_account_cannot_send() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        testing_env!(context.attached_deposit(0).build());
+        contract.freeze(accounts(1));
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_after_blacklist_remove() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.blacklist_add(accounts(1));
+        contract.blacklist_remove(accounts(1));
+        assert!(!contract.is_blacklisted(accounts(1)));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    fn setup_whitelist() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_registered_receiver();
+        register_account(&mut context, &mut contract, accounts(3));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_without_whitelist_mode() {
+        let (mut context, mut contract) = setup_whitelist();
+        assert!(!contract.is_whitelist_mode());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(3), 100.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_WHITELISTED: Sender is not whitelisted")]
+    fn test_transfer_not_whitelisted_in_whitelist_mode() {
+        let (mut context, mut contract) = setup_whitelist();
+        contract.set_whitelist_mode(true);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(3), 100.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_whitelisted_in_whitelist_mode() {
+        let (mut context, mut contract) = setup_whitelist();
+        contract.set_whitelist_mode(true);
+        contract.whitelist_add(accounts(1));
+        assert!(contract.is_whitelisted(accounts(1)));
+        assert!(contract.is_whitelisted(accounts(2)));
+        assert!(!contract.is_whitelisted(accounts(3)));
+
+        // The owner is whitelisted implicitly.
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 100.into(), None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ft_transfer(accounts(1), 100.into(), None);
+
+        contract.whitelist_add(accounts(3));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(3), 100.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 100);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.whitelist_remove(accounts(3));
+        contract.set_whitelist_mode(false);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(3), 100.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 200);
+    }
+
+    fn setup_capped_supply() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            Some((TOTAL_SUPPLY + 1_000).into()),
+            None,
+        );
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_mint_up_to_max_supply() {
+        let (_, mut contract) = setup_capped_supply();
+        assert_eq!(contract.get_max_supply(), Some((TOTAL_SUPPLY + 1_000).into()));
+        contract.ft_mint(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
+
+    fn setup_mint_deadline() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            test_metadata(),
+            Some(InitConfig { mint_deadline_ns: Some(1_000.into()), ..Default::default() }),
+        );
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_mint_before_deadline() {
+        let (mut context, mut contract) = setup_mint_deadline();
+        assert_eq!(contract.mint_deadline(), Some(1_000.into()));
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.ft_mint(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MINT_DEADLINE_PASSED: The mint deadline has passed")]
+    fn test_mint_after_deadline() {
+        let (mut context, mut contract) = setup_mint_deadline();
+        contract.set_mint_deadline(500.into());
+        testing_env!(context.block_timestamp(501).build());
+        contract.ft_mint(accounts(2), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The mint deadline can only be moved earlier")]
+    fn test_extend_mint_deadline() {
+        let (_, mut contract) = setup_mint_deadline();
+        contract.set_mint_deadline(1_001.into());
+    }
+
+    fn setup_mint_cap() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_capped_supply();
+        contract.set_mint_cap_per_epoch(100.into());
+        contract.set_epoch_length(1_000.into());
+        testing_env!(context.block_timestamp(1_500).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_mint_up_to_epoch_cap() {
+        let (mut context, mut contract) = setup_mint_cap();
+        contract.ft_mint(accounts(2), 60.into(), None);
+        contract.ft_mint(accounts(2), 40.into(), None);
+
+        testing_env!(context.block_timestamp(2_000).build());
+        contract.ft_mint(accounts(2), 100.into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 200);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR_MINT_CAP_EXCEEDED: Minting would exceed the cap of the current epoch"
+    )]
+    fn test_mint_over_epoch_cap() {
+        let (mut context, mut contract) = setup_mint_cap();
+        contract.ft_mint(accounts(2), 60.into(), None);
+        testing_env!(context.block_timestamp(1_999).build());
+        contract.ft_mint(accounts(2), 41.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MAX_SUPPLY_EXCEEDED: Minting would exceed the max supply")]
+    fn test_mint_over_max_supply() {
+        let (_, mut contract) = setup_capped_supply();
+        contract.ft_mint(accounts(2), 1_001.into(), None);
+    }
+
+    #[test]
+    fn test_mint_unbounded() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert_eq!(contract.get_max_supply(), None);
+        contract.ft_mint(accounts(2), u128::from(u64::MAX).into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + u128::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_transfer_batch_consolidates_events() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        register_account(&mut context, &mut contract, accounts(3));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(3), accounts(1)],
+            vec![100.into(), 200.into(), 300.into()],
+            None,
+        );
+
+        let logs = near_sdk::test_utils::get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: near_sdk::serde_json::Value =
+            near_sdk::serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(
+            event["data"],
+            near_sdk::serde_json::json!([
+                { "old_owner_id": accounts(2), "new_owner_id": accounts(1), "amount": "400" },
+                { "old_owner_id": accounts(2), "new_owner_id": accounts(3), "amount": "200" },
+            ])
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 400);
+    }
+
+    #[test]
+    fn test_event_version() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        assert_eq!(contract.get_event_version(), "1.0.0");
+        contract.set_event_version("1.1.0".to_string());
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        let logs = near_sdk::test_utils::get_logs();
+        let event: near_sdk::serde_json::Value =
+            near_sdk::serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["standard"], "nep141");
+        assert_eq!(event["version"], "1.1.0");
+        assert_eq!(event["event"], "ft_transfer");
+    }
+
+    #[test]
+    fn test_transfer_batch() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        register_account(&mut context, &mut contract, accounts(3));
+        register_account(&mut context, &mut contract, accounts(4));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(3), accounts(4)],
+            vec![100.into(), 200.into(), 300.into()],
+            Some("airdrop".to_string()),
+        );
+
+        let logs = near_sdk::test_utils::get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with(
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer""#
+        ));
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 200);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 300);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 600);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR_BATCH_LENGTH_MISMATCH: The number of recipients and amounts should be equal"
+    )]
+    fn test_transfer_batch_length_mismatch() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer_batch(vec![accounts(1)], vec![100.into(), 200.into()], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_REGISTERED: The account danny is not registered")]
+    fn test_transfer_batch_unregistered_recipient() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(3)],
+            vec![100.into(), 200.into()],
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_EMPTY_BATCH: The list of recipients is empty")]
+    fn test_transfer_batch_empty() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_batch(vec![], vec![], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SAME_ACCOUNT: Sender and receiver should be different")]
+    fn test_transfer_batch_to_self() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_batch(vec![accounts(2)], vec![100.into()], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ZERO_AMOUNT: The amount should be a positive number")]
+    fn test_transfer_batch_zero_amount() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_batch(vec![accounts(1)], vec![0.into()], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_OVERFLOW: Total amount overflow")]
+    fn test_transfer_batch_overflow() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(1)],
+            vec![u128::MAX.into(), 1.into()],
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_BALANCE: The account doesn't have enough balance")]
+    fn test_transfer_more_than_balance() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY + 1).into(), None);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR_INSUFFICIENT_STORAGE: The attached deposit is less than the minimum storage balance"
+    )]
+    fn test_storage_deposit_below_minimum() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context
+            .attached_deposit(contract.storage_balance_bounds().min.0 - 1)
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    fn test_transfer_call_with_configured_gas() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_ft_on_transfer_gas(Some(50_000_000_000_000.into()));
+        assert_eq!(contract.get_ft_on_transfer_gas(), Some(50_000_000_000_000.into()));
+        testing_env!(context.attached_deposit(1).prepaid_gas(Gas(100_000_000_000_000)).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), None, String::new());
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        let actions = format!("{:?}", receipts[0].actions);
+        assert!(actions.contains("ft_on_transfer"));
+        assert!(actions.contains("50000000000000"));
+    }
+
+    #[test]
+    #[should_panic(expected = "More gas is required")]
+    fn test_transfer_call_below_configured_gas() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_ft_on_transfer_gas(Some(300_000_000_000_000.into()));
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), None, String::new());
+    }
+
+    #[test]
+    fn test_transfer_call_to_allowed_receiver() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.transfer_call_allowlist_add(accounts(1));
+        assert_eq!(contract.get_transfer_call_allowlist(), vec![accounts(1)]);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), None, String::new());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob can't receive transfer calls")]
+    fn test_transfer_call_to_disallowed_receiver() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.transfer_call_allowlist_add(accounts(3));
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), None, String::new());
+    }
+
+    #[test]
+    fn test_storage_deposit_with_registration_premium() {
+        let (mut context, mut contract) = setup_with_fee(0);
+        contract.set_registration_premium(1_000.into());
+        assert_eq!(contract.get_registration_premium().0, 1_000);
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance + 1_500)
+            .predecessor_account_id(accounts(4))
+            .build());
+        contract.storage_deposit(None, None);
+        assert!(contract.is_registered(accounts(4)));
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, accounts(3));
+        assert!(format!("{:?}", receipts[0].actions).contains("deposit: 1000 "));
+        assert_eq!(receipts[1].receiver_id, accounts(4));
+        assert!(format!("{:?}", receipts[1].actions).contains("deposit: 500 "));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR_INSUFFICIENT_STORAGE: The attached deposit doesn't cover the registration premium of 1000 yoctoNEAR"
+    )]
+    fn test_storage_deposit_without_registration_premium() {
+        let (mut context, mut contract) = setup_with_fee(0);
+        contract.set_registration_premium(1_000.into());
+        testing_env!(context
+            .attached_deposit(contract.storage_balance_bounds().min.0 + 999)
+            .predecessor_account_id(accounts(4))
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    fn test_storage_deposit_registers_account() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let min_balance = contract.storage_balance_bounds().min;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance.0 * 2)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let storage_balance = contract.storage_deposit(None, None);
+        assert_eq!(storage_balance.total, min_balance);
+        assert_eq!(storage_balance.available.0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.ft_holders_count(), 2);
+    }
+
+    #[test]
+    fn test_storage_deposit_batch() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance * 4)
+            .build());
+        contract.storage_deposit_batch(vec![accounts(1), accounts(3), accounts(4), accounts(2)]);
+        assert!(contract.is_registered(accounts(1)));
+        assert!(contract.is_registered(accounts(3)));
+        assert!(contract.is_registered(accounts(4)));
+        assert_eq!(contract.ft_holders_count(), 4);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert!(format!("{:?}", receipts[0].actions).contains(&min_balance.to_string()));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance)
+            .build());
+        let storage_usage = env::storage_usage();
+        contract.storage_deposit_batch(vec![accounts(1), accounts(3)]);
+        assert_eq!(env::storage_usage(), storage_usage);
+        assert_eq!(contract.ft_holders_count(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_STORAGE: Must attach")]
+    fn test_storage_deposit_batch_without_enough_deposit() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context.attached_deposit(min_balance).build());
+        contract.storage_deposit_batch(vec![accounts(1), accounts(3)]);
+    }
+
+    #[test]
+    fn test_storage_deposit_already_registered() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        let storage_usage = env::storage_usage();
+        testing_env!(context
+            .storage_usage(storage_usage)
+            .attached_deposit(contract.storage_balance_bounds().min.0)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let storage_balance = contract.storage_deposit(None, Some(true));
+        assert_eq!(storage_balance.total, contract.storage_balance_bounds().min);
+        assert_eq!(env::storage_usage(), storage_usage);
+        assert_eq!(contract.ft_holders_count(), 2);
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec!["The account is already registered, refunding the deposit".to_string()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ONE_YOCTO: Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_transfer_without_deposit() {
+        let (_, mut contract) = setup_registered_receiver();
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_with_max_memo_len() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), Some("m".repeat(256)));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    fn test_transfer_without_memo_when_not_required() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        assert!(!contract.is_memo_required());
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    fn test_transfer_with_required_memo() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_require_memo(true);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), Some("invoice 42".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MEMO_REQUIRED: Transfers require a memo")]
+    fn test_transfer_without_required_memo() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_require_memo(true);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MEMO_REQUIRED: Transfers require a memo")]
+    fn test_transfer_call_with_empty_required_memo() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_require_memo(true);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), Some(String::new()), String::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MEMO_TOO_LONG: The memo can't be longer than 256 bytes")]
+    fn test_transfer_with_too_long_memo() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), Some("m".repeat(257)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MEMO_TOO_LONG: The memo can't be longer than 8 bytes")]
+    fn test_transfer_call_with_configured_memo_len() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_max_memo_len(8);
+        assert_eq!(contract.get_max_memo_len(), 8);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), Some("m".repeat(9)), String::new());
+    }
+
+    #[test]
+    fn test_is_registered() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert!(contract.is_registered(accounts(2)));
+        assert!(!contract.is_registered(accounts(4)));
+    }
+
+    #[test]
+    fn test_holders_count() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        assert_eq!(contract.ft_holders_count(), 2);
+        register_account(&mut context, &mut contract, accounts(3));
+        // Registering the same account again doesn't change the count.
+        register_account(&mut context, &mut contract, accounts(3));
+        assert_eq!(contract.ft_holders_count(), 3);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(3))
+            .build());
+        assert!(contract.storage_unregister(None));
+        assert_eq!(contract.ft_holders_count(), 2);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert!(contract.storage_unregister(Some(true)));
+        assert_eq!(contract.ft_holders_count(), 1);
+    }
+
+    #[test]
+    fn test_list_accounts() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        register_account(&mut context, &mut contract, accounts(3));
+        register_account(&mut context, &mut contract, accounts(4));
+
+        let first_page = contract.list_accounts(0, 3);
+        assert_eq!(first_page, vec![accounts(2), accounts(1), accounts(3)]);
+        let second_page = contract.list_accounts(3, 3);
+        assert_eq!(second_page, vec![accounts(4)]);
+        assert!(contract.list_accounts(4, 3).is_empty());
+    }
+
+    #[test]
+    fn test_minter_can_mint() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert!(contract.is_minter(accounts(2)));
+        assert!(!contract.is_minter(accounts(3)));
+        contract.add_minter(accounts(3));
+        assert!(contract.is_minter(accounts(3)));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_MINTER: Only the owner or a minter can call this method")]
+    fn test_removed_minter_cannot_mint() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.add_minter(accounts(3));
+        contract.remove_minter(accounts(3));
+        assert!(!contract.is_minter(accounts(3)));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(2), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_set_metadata() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let mut metadata = contract.ft_metadata();
+        metadata.name = "Renamed token".to_string();
+        contract.set_metadata(metadata);
+        assert_eq!(contract.ft_metadata().name, "Renamed token");
+
+        contract.set_icon(None);
+        assert_eq!(contract.ft_metadata().icon, None);
+
+        let reference_hash = Base64VecU8(vec![7; 32]);
+        contract.set_reference(Some("ipfs://token".to_string()), Some(reference_hash.clone()));
+        assert!(near_sdk::test_utils::get_logs()
+            .contains(&"Reference set to Some(\"ipfs://token\") by @charlie".to_string()));
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.reference, Some("ipfs://token".to_string()));
+        assert_eq!(metadata.reference_hash, Some(reference_hash));
+        assert_eq!(metadata.name, "Renamed token");
+    }
+
+    #[test]
+    #[should_panic(expected = "The reference hash has to be a 32 bytes SHA-256 hash, got 31 bytes")]
+    fn test_set_reference_with_wrong_hash_length() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.set_reference(Some("ipfs://token".to_string()), Some(Base64VecU8(vec![7; 31])));
+    }
+
+    #[test]
+    #[should_panic(expected = "Decimals can't be changed")]
+    fn test_set_metadata_decimals() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let mut metadata = contract.ft_metadata();
+        metadata.decimals = 18;
+        contract.set_metadata(metadata);
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert_eq!(contract.get_rename_cooldown().0, 30 * DAY_NS);
+        contract.rename("Renamed token".to_string(), "RNM".to_string());
+        assert_eq!(contract.ft_metadata().name, "Renamed token");
+        assert_eq!(contract.ft_metadata().symbol, "RNM");
+
+        testing_env!(context.block_timestamp(30 * DAY_NS).build());
+        contract.rename("Rebranded token".to_string(), "RBR".to_string());
+        assert_eq!(contract.ft_metadata().name, "Rebranded token");
+        assert_eq!(contract.ft_metadata().symbol, "RBR");
+    }
+
+    #[test]
+    #[should_panic(expected = "The token can be renamed again at 2592000000000001")]
+    fn test_rename_before_cooldown() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.block_timestamp(1).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.rename("Renamed token".to_string(), "RNM".to_string());
+        contract.rename("Rebranded token".to_string(), "RBR".to_string());
+    }
+
+    fn setup_locked_metadata() -> Contract {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        assert!(!contract.is_metadata_locked());
+        contract.lock_metadata();
+        assert!(contract.is_metadata_locked());
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "The metadata is locked")]
+    fn test_set_metadata_after_lock() {
+        let mut contract = setup_locked_metadata();
+        let mut metadata = contract.ft_metadata();
+        metadata.name = "Renamed token".to_string();
+        contract.set_metadata(metadata);
+    }
+
+    #[test]
+    #[should_panic(expected = "The metadata is locked")]
+    fn test_set_icon_after_lock() {
+        let mut contract = setup_locked_metadata();
+        contract.set_icon(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The metadata is locked")]
+    fn test_rename_after_lock() {
+        let mut contract = setup_locked_metadata();
+        contract.rename("Renamed token".to_string(), "RNM".to_string());
+    }
+
+    #[test]
+    fn test_set_locale_metadata() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let default_metadata = contract.ft_metadata();
+        let mut metadata = contract.ft_metadata();
+        metadata.name = "Ficha de ejemplo".to_string();
+        metadata.symbol = "FICHA".to_string();
+        contract.set_locale_metadata("es".to_string(), metadata);
+
+        let metadata = contract.ft_metadata_locale("es".to_string()).unwrap();
+        assert_eq!(metadata.name, "Ficha de ejemplo");
+        assert_eq!(metadata.symbol, "FICHA");
+        assert!(contract.ft_metadata_locale("fr".to_string()).is_none());
+        assert_eq!(contract.ft_metadata().name, default_metadata.name);
+        assert_eq!(contract.ft_metadata().symbol, default_metadata.symbol);
+    }
+
+    #[test]
+    #[should_panic(expected = "Decimals can't be changed")]
+    fn test_set_locale_metadata_decimals() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        let mut metadata = contract.ft_metadata();
+        metadata.decimals = 18;
+        contract.set_locale_metadata("es".to_string(), metadata);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_set_icon_not_owner() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_icon(None);
+    }
+
+    fn setup_with_fee(fee_basis_points: u16) -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            test_metadata(),
+            Some(InitConfig {
+                treasury_id: Some(accounts(3)),
+                fee_basis_points,
+                ..Default::default()
+            }),
+        );
+        register_account(&mut context, &mut contract, accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_with_fee() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        assert_eq!(contract.get_treasury(), accounts(3));
+        assert_eq!(contract.get_fee_basis_points(), 250);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 250);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 10_250);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_transfer_fee_split_between_recipients() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        register_account(&mut context, &mut contract, accounts(4));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_fee_recipients(vec![(accounts(3), 7_000), (accounts(4), 3_000)]);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 175);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 75);
+
+        // A fee of 101 splits into 70.7 and 30.3, and the dust goes to the first recipient.
+        contract.ft_transfer(accounts(1), 4_040.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 175 + 71);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 75 + 30);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_max_transferable_with_fee() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        let balance = contract.ft_balance_of(accounts(1)).0;
+
+        // 9757 pays a fee of 243 on top, 9758 would pay 243 as well and exceed the balance.
+        let max_amount = contract.max_transferable(accounts(1));
+        assert_eq!(max_amount.0, 9_757);
+        let preview = contract.simulate_transfer(accounts(1), accounts(2), max_amount);
+        assert_eq!(max_amount.0 + preview.fee.0, balance);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), max_amount, None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_max_transferable_with_fee_and_burn() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.set_burn_basis_points(100);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        let balance = contract.ft_balance_of(accounts(1)).0;
+        assert_eq!(balance, 10_000);
+
+        // 9663 pays a fee of 241 and burns 96 on top, 9664 would pay the same and exceed the
+        // balance.
+        let max_amount = contract.max_transferable(accounts(1));
+        assert_eq!(max_amount.0, 9_663);
+        let preview = contract.simulate_transfer(accounts(1), accounts(2), max_amount);
+        assert!(preview.would_succeed);
+        assert_eq!(preview.net_received.0 + preview.fee.0 + preview.burn.0, balance);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), max_amount, None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.max_transferable(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_transfer_batch_charges_fee() {
+        let (_, mut contract) = setup_with_fee(250);
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(1)],
+            vec![1_000.into(), 2_000.into()],
+            None,
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000 + 2_000);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 25 + 50);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 3_075);
+    }
+
+    #[test]
+    fn test_transfer_call_charges_fee_up_front() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        testing_env!(context.prepaid_gas(Gas(300_000_000_000_000)).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), None, String::new());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 25);
+
+        // The receiver returns everything, the fee isn't refunded.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"1000\"".to_vec())]
+        );
+        let used_amount = contract.ft_resolve_transfer(accounts(2), accounts(1), 1_000.into());
+        assert_eq!(used_amount.0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 25);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 25);
+    }
+
+    #[test]
+    fn test_transfer_batch_burns() {
+        let (_, mut contract) = setup_with_fee(0);
+        contract.set_burn_basis_points(100);
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(1)],
+            vec![1_000.into(), 2_000.into()],
+            None,
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000 + 2_000);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 3_030);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 10 - 20);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_transfer_call_burns_up_front() {
+        let (mut context, mut contract) = setup_with_fee(0);
+        contract.set_burn_basis_points(100);
+        testing_env!(context.prepaid_gas(Gas(300_000_000_000_000)).build());
+        contract.ft_transfer_call(accounts(1), 1_000.into(), None, String::new());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 10);
+
+        // The receiver returns everything, the burned part stays burned.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"1000\"".to_vec())]
+        );
+        contract.ft_resolve_transfer(accounts(2), accounts(1), 1_000.into());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 10);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 10);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_max_transferable_within_daily_limit() {
+        let (mut context, mut contract) = setup_daily_limit();
+        contract.set_min_transfer_amount(100.into());
+        assert_eq!(contract.max_transferable(accounts(2)).0, 1_000);
+        contract.ft_transfer(accounts(1), 950.into(), None);
+        assert_eq!(contract.max_transferable(accounts(2)).0, 0);
+
+        testing_env!(context.block_timestamp(2 * DAY_NS).build());
+        assert_eq!(contract.max_transferable(accounts(2)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "The weights of the fee recipients must sum to 10000")]
+    fn test_fee_recipient_weights_must_sum_to_10000() {
+        let (_, mut contract) = setup_with_fee(250);
+        contract.set_fee_recipients(vec![(accounts(3), 7_000), (accounts(1), 2_000)]);
+    }
+
+    #[test]
+    fn test_lock_funding_skips_fee_and_burn() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.set_burn_basis_points(100);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        contract.create_lock(accounts(1), 10_000.into(), 0.into(), 1_000.into());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.spendable_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    fn test_circulating_supply_excludes_treasury() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY);
+
+        contract.add_excluded_account(accounts(3));
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 250);
+        contract.add_excluded_account(accounts(2));
+        assert_eq!(contract.circulating_supply().0, 10_000);
+        contract.remove_excluded_account(accounts(2));
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 250);
+    }
+
+    fn setup_min_transfer_amount() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_min_transfer_amount(100.into());
+        testing_env!(context.attached_deposit(1).build());
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_BELOW_MINIMUM: Amount below minimum")]
+    fn test_transfer_below_min_amount() {
+        let (_, mut contract) = setup_min_transfer_amount();
+        contract.ft_transfer(accounts(1), 99.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_min_amount() {
+        let (_, mut contract) = setup_min_transfer_amount();
+        assert_eq!(contract.get_min_transfer_amount().0, 100);
+        contract.ft_transfer(accounts(1), 100.into(), None);
+        contract.ft_transfer(accounts(1), 101.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 201);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_BELOW_MINIMUM: Amount below minimum")]
+    fn test_transfer_call_below_min_amount() {
+        let (_, mut contract) = setup_min_transfer_amount();
+        contract.ft_transfer_call(accounts(1), 99.into(), None, String::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_BELOW_MINIMUM: Amount below minimum")]
+    fn test_transfer_batch_below_min_amount() {
+        let (_, mut contract) = setup_min_transfer_amount();
+        contract.ft_transfer_batch(
+            vec![accounts(1), accounts(1)],
+            vec![U128(1_000), U128(99)],
+            None,
+        );
+    }
+
+    #[test]
+    fn test_transfer_with_burn() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_burn_basis_points(100);
+        assert_eq!(contract.get_burn_basis_points(), 100);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+        // Rounds down, so at most the configured share is burned.
+        contract.ft_transfer(accounts(1), 199.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000 + 199);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 10_100 - 200);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 101);
+        contract.debug_assert_supply_consistent();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_BALANCE: The account doesn't have enough balance")]
+    fn test_transfer_with_burn_over_balance() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_burn_basis_points(100);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), TOTAL_SUPPLY.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The burn can't exceed 2000 basis points")]
+    fn test_set_burn_too_high() {
+        let (_, mut contract) = setup_registered_receiver();
+        contract.set_burn_basis_points(2_001);
+    }
+
+    fn setup_daily_limit() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_daily_limit(Some(1_000.into()));
+        testing_env!(context.attached_deposit(1).block_timestamp(DAY_NS + 1).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_daily_limit() {
+        let (mut context, mut contract) = setup_daily_limit();
+        assert_eq!(contract.get_daily_limit(), Some(1_000.into()));
+        contract.ft_transfer(accounts(1), 400.into(), None);
+        contract.ft_transfer(accounts(1), 600.into(), None);
+
+        testing_env!(context.block_timestamp(2 * DAY_NS).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 2_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DAILY_LIMIT_EXCEEDED: The transfer exceeds the daily limit")]
+    fn test_daily_limit_exceeded() {
+        let (mut context, mut contract) = setup_daily_limit();
+        contract.ft_transfer(accounts(1), 400.into(), None);
+        testing_env!(context.block_timestamp(2 * DAY_NS - 1).build());
+        contract.ft_transfer(accounts(1), 601.into(), None);
+    }
+
+    fn setup_transfer_cooldown() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_transfer_cooldown((60 * 1_000_000_000).into());
+        testing_env!(context.attached_deposit(1).block_timestamp(1).build());
+        // The owner is exempt from the cooldown.
+        contract.ft_transfer(accounts(1), 5_000.into(), None);
+        contract.ft_transfer(accounts(1), 5_000.into(), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_cooldown() {
+        let (mut context, mut contract) = setup_transfer_cooldown();
+        assert_eq!(contract.get_transfer_cooldown().0, 60 * 1_000_000_000);
+        testing_env!(context.block_timestamp(60 * 1_000_000_000 + 1).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 8_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TRANSFER_COOLDOWN: The next transfer is allowed in 1 ns")]
+    fn test_transfer_cooldown_not_elapsed() {
+        let (mut context, mut contract) = setup_transfer_cooldown();
+        testing_env!(context.block_timestamp(60 * 1_000_000_000).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_auto_pause_threshold(Some(100));
+        assert_eq!(contract.get_auto_pause_threshold(), Some(100));
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 100).into(), None);
+        assert!(!contract.is_paused());
+
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 100 + 1).into(), None);
+        assert!(contract.is_paused());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY / 100);
+        assert!(near_sdk::test_utils::get_logs()
+            .last()
+            .unwrap()
+            .starts_with("WARNING: The transfer"));
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert!(format!("{:?}", receipts[0].actions).contains("reject_auto_paused_transfer"));
+
+        contract.unpause();
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY / 100 + 1_000);
+    }
+
+    #[test]
+    fn test_circuit_breaker_fails_transfer_call() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_auto_pause_threshold(Some(100));
+        testing_env!(context.attached_deposit(1).prepaid_gas(Gas(300_000_000_000_000)).build());
+        let result =
+            contract.ft_transfer_call(accounts(1), (TOTAL_SUPPLY / 2).into(), None, String::new());
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+        drop(result);
+        assert!(contract.is_paused());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert!(format!("{:?}", receipts[0].actions).contains("reject_auto_paused_transfer"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AUTO_PAUSED: The transfer exceeds the auto-pause threshold")]
+    fn test_reject_auto_paused_transfer() {
+        let (mut context, contract) = setup_registered_receiver();
+        testing_env!(context.predecessor_account_id(env::current_account_id()).build());
+        contract.reject_auto_paused_transfer(1_000.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_BALANCE: The account doesn't have enough balance")]
+    fn test_circuit_breaker_ignores_uncovered_transfers() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_auto_pause_threshold(Some(100));
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer_batch(vec![accounts(2)], vec![TOTAL_SUPPLY.into()], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED: Receiver is blacklisted")]
+    fn test_circuit_breaker_ignores_rejected_transfers() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_auto_pause_threshold(Some(100));
+        contract.blacklist_add(accounts(1));
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 2).into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LOCKED_BALANCE: The amount exceeds the unlocked balance")]
+    fn test_circuit_breaker_ignores_locked_tokens() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_auto_pause_threshold(Some(100));
+        testing_env!(context.attached_deposit(env::storage_byte_cost() * 1_000).build());
+        contract.create_lock(accounts(1), (TOTAL_SUPPLY / 2).into(), 0.into(), 1_000.into());
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), (TOTAL_SUPPLY / 2).into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_circuit_breaker_transfer_call_requires_one_yocto() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_auto_pause_threshold(Some(100));
+        testing_env!(context.attached_deposit(0).prepaid_gas(Gas(300_000_000_000_000)).build());
+        contract.ft_transfer_call(accounts(1), (TOTAL_SUPPLY / 2).into(), None, String::new());
+    }
+
+    #[test]
+    fn test_transfer_fee_rounds_up() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.set_burn_basis_points(100);
+        contract.set_fee_round_up(true);
+        assert!(contract.is_fee_round_up());
+        // 2.5% of 39 is 0.975 and 1% is 0.39, both are rounded up to 1.
+        contract.ft_transfer(accounts(1), 39.into(), None);
+        // 2.5% of 400 is exactly 10 and 1% is exactly 4, which aren't rounded.
+        contract.ft_transfer(accounts(1), 400.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 39 + 400);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 1 + 10);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1 - 4);
+    }
+
+    #[test]
+    fn test_transfer_fee_and_burn_round_down() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.set_burn_basis_points(100);
+        contract.ft_transfer(accounts(1), 139.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        // 2.5% of 139 is 3.475 and 1% is 1.39.
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 139);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 3);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1);
+    }
+
+    #[test]
+    fn test_transfer_fee_rounds_down() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.ft_transfer(accounts(1), 39.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 39);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 0);
+    }
+
+    #[test]
+    fn test_transfer_fee_of_max_amount() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(2),
+            u128::MAX.into(),
+            test_metadata(),
+            Some(InitConfig {
+                treasury_id: Some(accounts(3)),
+                fee_basis_points: 250,
+                burn_basis_points: 100,
+                ..Default::default()
+            }),
+        );
+        register_account(&mut context, &mut contract, accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        // 90% of u128::MAX, so the fee and the burn on top still fit the balance.
+        let amount = 30625413022884461711703714668859138 * 10_000 + 1_455;
+        let receipt = match contract.ft_transfer_detailed(accounts(1), amount.into(), None) {
+            PromiseOrValue::Value(receipt) => receipt,
+            PromiseOrValue::Promise(_) => panic!("The transfer tripped the circuit breaker"),
+        };
+        assert_eq!(receipt.fee.0, 30625413022884461711703714668859138 * 250 + 36);
+        assert_eq!(receipt.burn.0, 30625413022884461711703714668859138 * 100 + 14);
+        assert_eq!(contract.ft_balance_of(accounts(1)), receipt.net);
+        assert_eq!(contract.ft_total_supply().0, u128::MAX - receipt.burn.0);
+    }
+
+    #[test]
+    fn test_transfer_zero_fee() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        let index = contract.set_fee_basis_points(0);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(crate::timelock::MIN_ACTION_DELAY_NS)
+            .build());
+        contract.execute_action(index);
+        contract.ft_transfer(accounts(1), 10_000.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 10_000);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The fee can't exceed 1000 basis points")]
+    fn test_set_fee_above_cap() {
+        let (_, mut contract) = setup_with_fee(250);
+        contract.set_fee_basis_points(1_001);
+    }
+
+    #[test]
+    fn test_simulate_transfer() {
+        let (mut context, contract) = setup_with_fee(250);
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        let preview = contract.simulate_transfer(accounts(2), accounts(1), 10_000.into());
+        assert_eq!(
+            preview,
+            TransferPreview {
+                would_succeed: true,
+                fee: 250.into(),
+                burn: 0.into(),
+                net_received: 10_000.into(),
+                from_balance_after: (TOTAL_SUPPLY - 10_250).into(),
+                reason: None,
+            }
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_transfer_detailed_matches_simulation() {
+        let (mut context, mut contract) = setup_with_fee(250);
+        contract.set_burn_basis_points(100);
+        let preview = contract.simulate_transfer(accounts(2), accounts(1), 10_000.into());
+        testing_env!(context.attached_deposit(1).build());
+        let receipt = match contract.ft_transfer_detailed(accounts(1), 10_000.into(), None) {
+            PromiseOrValue::Value(receipt) => receipt,
+            PromiseOrValue::Promise(_) => panic!("The transfer tripped the circuit breaker"),
+        };
+        assert_eq!(
+            receipt,
+            TransferReceipt { net: preview.net_received, fee: preview.fee, burn: preview.burn }
+        );
+        assert_eq!(receipt.net.0, 10_000);
+        assert_eq!(contract.ft_balance_of(accounts(1)), receipt.net);
+        assert_eq!(contract.ft_balance_of(accounts(2)), preview.from_balance_after);
+    }
+
+    #[test]
+    fn test_simulate_transfer_blacklisted_sender() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.blacklist_add(accounts(2));
+        testing_env!(context.is_view(true).build());
+        let preview = contract.simulate_transfer(accounts(2), accounts(1), 1_000.into());
+        assert!(!preview.would_succeed);
+        assert_eq!(preview.reason.as_deref(), Some("ERR_BLACKLISTED: Sender is blacklisted"));
+        assert_eq!(preview.net_received.0, 0);
+        assert_eq!(preview.from_balance_after.0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_balances_of() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        let balances = contract.ft_balances_of(vec![accounts(1), accounts(3), accounts(2)]);
+        assert_eq!(balances, vec![1_000.into(), 0.into(), (TOTAL_SUPPLY - 1_000).into()]);
+    }
+
+    #[test]
+    fn test_transfer_requires_registration() {
+        let (_, mut contract) = setup_registered_receiver();
+        let min_balance = contract.storage_balance_bounds().min;
+        assert_eq!(contract.transfer_requires_registration(accounts(1)), None);
+        assert_eq!(contract.transfer_requires_registration(accounts(3)), Some(min_balance));
+
+        contract.set_registration_premium(1_000.into());
+        assert_eq!(
+            contract.transfer_requires_registration(accounts(3)),
+            Some((min_balance.0 + 1_000).into())
+        );
+    }
+
+    #[test]
+    fn test_transfer_count() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        assert_eq!(contract.transfer_count(accounts(2)), 1);
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        contract.ft_transfer_batch(vec![accounts(1)], vec![1_000.into()], None);
+        assert_eq!(contract.transfer_count(accounts(2)), 3);
+        assert_eq!(contract.transfer_count(accounts(1)), 0);
+    }
+
+    #[test]
+    fn test_transfer_count_reset_on_close() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.transfer_count(accounts(1)), 1);
+        assert!(contract.storage_unregister(None));
+        assert_eq!(contract.transfer_count(accounts(1)), 0);
+    }
+
+    #[test]
+    fn test_account_storage_key() {
+        let (_, contract) = setup_registered_receiver();
+        let key = contract.account_storage_key(accounts(1));
+        assert_eq!(key, contract.account_storage_key(accounts(1)));
+        assert_ne!(key, contract.account_storage_key(accounts(2)));
+        let balance = env::storage_read(&key.0).unwrap();
+        assert_eq!(
+            Balance::try_from_slice(&balance).unwrap(),
+            contract.ft_balance_of(accounts(1)).0
+        );
+    }
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(3_000_000, 6), "3");
+        assert_eq!(format_amount(1_500_000, 6), "1.5");
+        assert_eq!(format_amount(1_234_567, 6), "1.234567");
+        assert_eq!(format_amount(1, 6), "0.000001");
+        assert_eq!(format_amount(0, 6), "0");
+        assert_eq!(format_amount(42, 0), "42");
+    }
+
+    #[test]
+    fn test_balance_of_formatted() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_500_000.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of_formatted(accounts(1)), "1.5");
+        assert_eq!(contract.ft_balance_of_formatted(accounts(3)), "0");
+    }
+
+    #[test]
+    fn test_round_amount() {
+        let (_, contract) = setup_registered_receiver();
+        assert_eq!(contract.round_amount(1_234_567.into(), 2).0, 1_230_000);
+        assert_eq!(contract.round_amount(1_234_567.into(), 0).0, 1_000_000);
+        assert_eq!(contract.round_amount(1_234_567.into(), 6).0, 1_234_567);
+        assert_eq!(contract.round_amount(1_234_567.into(), 9).0, 1_234_567);
+        assert_eq!(contract.round_amount(9_999.into(), 2).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "At most 100 accounts can be queried at once")]
+    fn test_balances_of_too_many() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        contract.ft_balances_of(vec![accounts(1); 101]);
+    }
+
+    #[test]
+    fn test_force_close_account_burns_balance() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(1))
+            .build());
+        assert!(contract.storage_unregister(Some(true)));
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![
+                format!("Closed @{} with 1000", accounts(1)),
+                format!(
+                    r#"EVENT_JSON:{{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{{"owner_id":"{}","amount":"1000","memo":"Account closed"}}]}}"#,
+                    accounts(1)
+                ),
+            ]
+        );
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_transfer_checked() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        let balance = contract.ft_transfer_checked(accounts(1), 1_000.into(), None);
+        assert_eq!(balance.0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(balance, contract.ft_balance_of(accounts(2)));
+    }
+
+    fn setup_owner_lock(duration_ns: u64) -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.block_timestamp(1_000).build());
+        let mut contract = Contract::new_default_meta(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            None,
+            Some(duration_ns.into()),
+        );
+        register_account(&mut context, &mut contract, accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LOCKED_BALANCE")]
+    fn test_owner_lock_blocks_full_transfer() {
+        let (_, mut contract) = setup_owner_lock(1_000);
+        assert_eq!(contract.locked_balance_of(accounts(2)).0, TOTAL_SUPPLY);
+        contract.ft_transfer(accounts(1), TOTAL_SUPPLY.into(), None);
+    }
+
+    #[test]
+    fn test_owner_lock_vests_linearly() {
+        let (mut context, mut contract) = setup_owner_lock(1_000);
+        testing_env!(context.block_timestamp(1_250).build());
+        assert_eq!(contract.claim_unlocked().0, TOTAL_SUPPLY / 4);
+        assert_eq!(contract.spendable_balance_of(accounts(2)).0, TOTAL_SUPPLY / 4);
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 4).into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY / 4);
+        assert_eq!(contract.locked_balance_of(accounts(2)).0, TOTAL_SUPPLY * 3 / 4);
+    }
+
+    fn event_logs() -> Vec<String> {
+        near_sdk::test_utils::get_logs()
+            .into_iter()
+            .filter(|log| log.starts_with("EVENT_JSON:"))
+            .collect()
+    }
+
+    #[test]
+    fn test_events_disabled() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        contract.set_emit_events(false);
+        assert!(!contract.is_emitting_events());
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        contract.ft_mint(accounts(1), 1_000.into(), None);
+        contract.ft_burn(500.into(), None);
+        assert!(event_logs().is_empty());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 2_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 500);
+    }
+
+    #[test]
+    fn test_events_enabled() {
+        let (mut context, mut contract) = setup_registered_receiver();
+        assert!(contract.is_emitting_events());
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        let logs = event_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#""event":"ft_transfer""#));
     }
 }
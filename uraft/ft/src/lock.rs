@@ -0,0 +1,386 @@
+//! Vesting locks. Locked tokens are part of the account balance, but only the released part of
+//! a lock can be transferred. Released tokens are claimed with `claim_unlocked`.
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq)]
+pub enum LockKind {
+    /// Tokens unlock linearly between `start_ts` and `start_ts + duration_ns`.
+    Linear,
+    /// Nothing unlocks before `start_ts`, and everything unlocks at `start_ts`.
+    Cliff,
+}
+
+/// A vesting schedule of `total` tokens. `released` tokens have been claimed already.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LockInfo {
+    pub kind: LockKind,
+    pub total: Balance,
+    pub start_ts: u64,
+    pub duration_ns: u64,
+    pub released: Balance,
+}
+
+impl LockInfo {
+    /// Returns the amount of tokens vested at `timestamp`.
+    pub fn vested_amount(&self, timestamp: u64) -> Balance {
+        if self.kind == LockKind::Cliff {
+            if timestamp >= self.start_ts {
+                self.total
+            } else {
+                0
+            }
+        } else if timestamp <= self.start_ts {
+            0
+        } else if timestamp - self.start_ts >= self.duration_ns {
+            self.total
+        } else {
+            let elapsed = Balance::from(timestamp - self.start_ts);
+            let duration = Balance::from(self.duration_ns);
+            // Splitting the multiplication keeps the intermediate values below `u128::MAX`.
+            self.total / duration * elapsed + self.total % duration * elapsed / duration
+        }
+    }
+
+    /// Returns the amount of tokens that can't be transferred yet.
+    pub fn locked_amount(&self) -> Balance {
+        self.total - self.released
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers `amount` tokens from the owner to `account_id` under a linear vesting schedule
+    /// that starts at `start_ts` and lasts `duration_ns` nanoseconds. Can only be called by the
+    /// owner. The attached deposit has to cover the storage of the lock, the rest is refunded.
+    #[payable]
+    pub fn create_lock(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        start_ts: U64,
+        duration_ns: U64,
+    ) {
+        assert!(duration_ns.0 > 0, "The duration should be a positive number");
+        self.internal_create_lock(
+            account_id,
+            LockInfo {
+                kind: LockKind::Linear,
+                total: amount.into(),
+                start_ts: start_ts.into(),
+                duration_ns: duration_ns.into(),
+                released: 0,
+            },
+        );
+    }
+
+    /// Transfers `amount` tokens from the owner to `account_id`, all of which unlock at
+    /// `cliff_ts`. Can only be called by the owner. The attached deposit has to cover the storage
+    /// of the lock, the rest is refunded.
+    #[payable]
+    pub fn create_cliff_lock(&mut self, account_id: AccountId, amount: U128, cliff_ts: U64) {
+        self.internal_create_lock(
+            account_id,
+            LockInfo {
+                kind: LockKind::Cliff,
+                total: amount.into(),
+                start_ts: cliff_ts.into(),
+                duration_ns: 0,
+                released: 0,
+            },
+        );
+    }
+
+    /// Releases the vested part of the predecessor's lock, so it can be transferred.
+    /// Returns the amount released by this call.
+    pub fn claim_unlocked(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let mut lock = self.locks.get(&account_id).expect("The account has no lock");
+        let vested = lock.vested_amount(env::block_timestamp());
+        let claimed = vested - lock.released;
+        lock.released = vested;
+        self.locked_total -= claimed;
+        if lock.locked_amount() == 0 {
+            self.locks.remove(&account_id);
+        } else {
+            self.locks.insert(&account_id, &lock);
+        }
+        log!("@{} claimed {} unlocked tokens", account_id, claimed);
+        claimed.into()
+    }
+
+    /// Stops the vesting of the lock of `account_id` and returns its unvested tokens to the owner.
+    /// The vested part stays claimable by the account. Returns the amount returned to the owner.
+    /// Can only be called by the owner. Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn revoke_lock(&mut self, account_id: AccountId) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(account_id != self.owner_id, "The owner's lock can't be revoked");
+        let mut lock = self.locks.get(&account_id).expect("The account has no lock");
+        let now = env::block_timestamp();
+        let vested = lock.vested_amount(now);
+        let revoked = lock.total - vested;
+        // The vested tokens unlock at once, since the schedule no longer applies to them.
+        lock.kind = LockKind::Cliff;
+        lock.total = vested;
+        lock.start_ts = now;
+        lock.duration_ns = 0;
+        self.locked_total -= revoked;
+        if lock.locked_amount() == 0 {
+            self.locks.remove(&account_id);
+        } else {
+            self.locks.insert(&account_id, &lock);
+        }
+        if revoked > 0 {
+            let owner_id = self.owner_id.clone();
+            self.internal_move_tokens(&account_id, &owner_id, revoked, Some("Lock revoked"));
+        }
+        log!("The lock of @{} is revoked, {} tokens returned to the owner", account_id, revoked);
+        revoked.into()
+    }
+
+    /// Returns the part of the balance of `account_id` that can't be transferred yet.
+    pub fn locked_balance_of(&self, account_id: AccountId) -> U128 {
+        self.internal_locked_balance_of(&account_id).into()
+    }
+
+    /// Returns the part of the balance of `account_id` that can be transferred now, i.e. the
+    /// balance without the tokens that are still locked or reserved by pending transfers.
+    pub fn spendable_balance_of(&self, account_id: AccountId) -> U128 {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        let reserved = self.internal_locked_balance_of(&account_id)
+            + self.internal_pending_balance_of(&account_id);
+        balance.saturating_sub(reserved).into()
+    }
+}
+
+impl Contract {
+    fn internal_create_lock(&mut self, account_id: AccountId, lock: LockInfo) {
+        self.assert_owner();
+        assert!(lock.total > 0, "The amount should be a positive number");
+        assert!(account_id != self.owner_id, "The owner can't lock tokens for itself");
+        if let Some(lock) = self.locks.get(&account_id) {
+            assert!(lock.locked_amount() == 0, "The account already has an active lock");
+        }
+        let initial_storage_usage = env::storage_usage();
+        let owner_id = self.owner_id.clone();
+        // The whole amount has to arrive to be locked, so no fee or burn is taken from it.
+        self.assert_transfer_allowed(&owner_id, &account_id, lock.total);
+        self.assert_spendable(&owner_id, lock.total);
+        self.internal_move_tokens(&owner_id, &account_id, lock.total, Some("Lock"));
+        self.locked_total += lock.total;
+        self.locks.insert(&account_id, &lock);
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    /// Locks the whole balance of the owner linearly over `duration_ns` from now. Used at
+    /// initialization, where the balance is the initial supply.
+    pub(crate) fn internal_lock_owner_supply(&mut self, duration_ns: u64) {
+        assert!(duration_ns > 0, "The lock duration should be a positive number");
+        let owner_id = self.owner_id.clone();
+        let total = self.token.accounts.get(&owner_id).unwrap_or(0);
+        assert!(total > 0, "There is no initial supply to lock");
+        let lock = LockInfo {
+            kind: LockKind::Linear,
+            total,
+            start_ts: env::block_timestamp(),
+            duration_ns,
+            released: 0,
+        };
+        self.locked_total += total;
+        self.locks.insert(&owner_id, &lock);
+    }
+
+    pub(crate) fn internal_locked_balance_of(&self, account_id: &AccountId) -> Balance {
+        self.locks.get(account_id).map_or(0, |lock| lock.locked_amount())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+    const START: u64 = 1_000;
+    const DURATION: u64 = 10_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.create_lock(accounts(1), 1_000.into(), START.into(), DURATION.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(1))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_nothing_released_before_start() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.block_timestamp(START).build());
+        assert_eq!(contract.claim_unlocked().0, 0);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    fn test_linear_release_midway() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.block_timestamp(START + DURATION / 4).build());
+        assert_eq!(contract.claim_unlocked().0, 250);
+        testing_env!(context.block_timestamp(START + DURATION / 2).build());
+        assert_eq!(contract.claim_unlocked().0, 250);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 500);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), 500.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 500);
+    }
+
+    #[test]
+    fn test_full_release_after_duration() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.block_timestamp(START + DURATION + 1).build());
+        assert_eq!(contract.claim_unlocked().0, 1_000);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 0);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), 1_000.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_circulating_supply_excludes_locked() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 1_000);
+        testing_env!(context.block_timestamp(START + DURATION / 4).build());
+        contract.claim_unlocked();
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 750);
+        testing_env!(context.block_timestamp(START + DURATION).build());
+        contract.claim_unlocked();
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LOCKED_BALANCE: The amount exceeds the unlocked balance")]
+    fn test_transfer_locked_tokens() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.block_timestamp(START + DURATION / 2).build());
+        contract.claim_unlocked();
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), 501.into(), None);
+    }
+
+    #[test]
+    fn test_spendable_balance() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.spendable_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.spendable_balance_of(accounts(2)).0, TOTAL_SUPPLY - 1_000);
+
+        testing_env!(context.block_timestamp(START + DURATION / 4).build());
+        contract.claim_unlocked();
+        assert_eq!(contract.spendable_balance_of(accounts(1)).0, 250);
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(2), 250.into(), None);
+        assert_eq!(contract.spendable_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account can't be unregistered while it has a lock")]
+    fn test_force_unregister_with_lock() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(1).build());
+        contract.storage_unregister(Some(true));
+    }
+
+    #[test]
+    fn test_revoke_lock_midway() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.block_timestamp(START + DURATION / 4).build());
+        assert_eq!(contract.claim_unlocked().0, 250);
+
+        testing_env!(context
+            .block_timestamp(START + DURATION * 3 / 5)
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        assert_eq!(contract.revoke_lock(accounts(1)).0, 400);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 600);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 600);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 350);
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 350);
+
+        // The vested part stays claimable and nothing vests after the revocation.
+        testing_env!(context
+            .block_timestamp(START + DURATION)
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(1))
+            .build());
+        assert_eq!(contract.claim_unlocked().0, 350);
+        assert_eq!(contract.locked_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 600);
+    }
+
+    #[test]
+    fn test_vested_amount_does_not_overflow() {
+        let lock = LockInfo {
+            kind: LockKind::Linear,
+            total: u128::MAX,
+            start_ts: 0,
+            duration_ns: 10,
+            released: 0,
+        };
+        assert_eq!(lock.vested_amount(5), u128::MAX / 2);
+    }
+
+    #[test]
+    fn test_cliff_lock() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.create_cliff_lock(accounts(3), 1_000.into(), START.into());
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 2_000);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(START - 1)
+            .build());
+        assert_eq!(contract.claim_unlocked().0, 0);
+        assert_eq!(contract.locked_balance_of(accounts(3)).0, 1_000);
+
+        testing_env!(context.block_timestamp(START).build());
+        assert_eq!(contract.claim_unlocked().0, 1_000);
+        assert_eq!(contract.locked_balance_of(accounts(3)).0, 0);
+        assert_eq!(contract.circulating_supply().0, TOTAL_SUPPLY - 1_000);
+    }
+}
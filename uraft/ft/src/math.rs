@@ -0,0 +1,30 @@
+//! Integer math shared by the fee, burn and reward computations.
+
+pub(crate) use self::u256::U256;
+
+// The generated code doesn't follow clippy's style.
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        /// A 256-bit unsigned integer, wide enough for the product of two balances.
+        pub struct U256(4);
+    }
+}
+
+/// Returns `a * b / c` rounded down. The product is computed on 256 bits, so it can't overflow,
+/// but the result has to fit in 128 bits, which holds when `a <= c` or `b <= c`.
+pub(crate) fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    (U256::from(a) * U256::from(b) / U256::from(c)).as_u128()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(200_000, 1_001, 1_000_000), 200);
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(mul_div(u128::MAX / 3, u128::MAX - 1, u128::MAX), u128::MAX / 3 - 1);
+    }
+}
@@ -0,0 +1,237 @@
+//! Gasless transfers. An account registers an ed25519 signing key once, after which a relayer can
+//! submit transfers the account signed off-chain. Every signed transfer carries the next nonce of
+//! the account, so a signature can't be replayed or applied out of order.
+//!
+//! The signed message is the Borsh serialization of
+//! `(contract_id, from, to, amount: u128, nonce: u64)`. The contract id keeps a signature from
+//! being replayed on another token.
+//!
+//! Closing an account removes its signing key but keeps its nonce, so the transfers it signed
+//! before can't be replayed if it registers again and sets the same key.
+use crate::*;
+use ed25519_dalek::Verifier;
+use std::convert::{TryFrom, TryInto};
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the ed25519 key that signs the predecessor's gasless transfers. `public_key` is the raw
+    /// 32 byte key. The attached deposit has to cover the storage of the key and the nonce, the
+    /// rest is refunded.
+    #[payable]
+    pub fn set_signing_key(&mut self, public_key: Base64VecU8) {
+        let account_id = env::predecessor_account_id();
+        require(
+            self.token.accounts.contains_key(&account_id),
+            ContractError::NotRegistered(account_id.clone()),
+        );
+        let public_key: [u8; 32] =
+            public_key.0.as_slice().try_into().expect("The public key should be 32 bytes long");
+        let initial_storage_usage = env::storage_usage();
+        self.signing_keys.insert(&account_id, &public_key);
+        if self.nonces.get(&account_id).is_none() {
+            self.nonces.insert(&account_id, &0);
+        }
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    /// Returns the nonce that the next signed transfer of `account_id` has to carry.
+    pub fn get_next_nonce(&self, account_id: AccountId) -> u64 {
+        self.nonces.get(&account_id).unwrap_or(0) + 1
+    }
+
+    /// Transfers `amount` tokens from `from` to `to` on behalf of `from`, who signed the transfer
+    /// with its signing key. `nonce` has to be the next nonce of `from`. Can be called by anyone,
+    /// the transfer goes through the same checks and fees as `ft_transfer`.
+    pub fn ft_transfer_with_signature(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        amount: U128,
+        nonce: u64,
+        signature: Base64VecU8,
+    ) {
+        let public_key = self.signing_keys.get(&from).expect("The account has no signing key");
+        let last_nonce = self.nonces.get(&from).unwrap_or(0);
+        assert!(nonce == last_nonce + 1, "The nonce should be {}", last_nonce + 1);
+        let message = (env::current_account_id(), from.clone(), to.clone(), amount.0, nonce)
+            .try_to_vec()
+            .unwrap();
+        assert!(ed25519_verify(&signature.0, &message, &public_key), "Invalid signature");
+        self.nonces.insert(&from, &nonce);
+        if let Some(rejection) = self.trip_circuit_breaker(&from, &to, amount.into()) {
+            rejection.as_return();
+            return;
+        }
+        self.internal_transfer(&from, &to, amount.into(), None);
+    }
+}
+
+impl Contract {
+    /// Removes the signing key of a closed account and refunds its storage to it. The nonce stays,
+    /// and so does the storage it was paid for.
+    pub(crate) fn internal_remove_signing_key(&mut self, account_id: &AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        self.signing_keys.remove(account_id);
+        let released = initial_storage_usage - env::storage_usage();
+        if released > 0 {
+            Promise::new(account_id.clone())
+                .transfer(Balance::from(released) * env::storage_byte_cost());
+        }
+    }
+}
+
+/// Checks an ed25519 `signature` of `message`. The SDK used by this contract has no host function
+/// for it, so it's verified in the contract.
+pub(crate) fn ed25519_verify(signature: &[u8], message: &[u8], public_key: &[u8; 32]) -> bool {
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => public_key.verify(message, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn sign(from: AccountId, to: AccountId, amount: Balance, nonce: u64) -> Base64VecU8 {
+        let message = (accounts(0), from, to, amount, nonce).try_to_vec().unwrap();
+        keypair().sign(&message).to_bytes().to_vec().into()
+    }
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_signing_key(keypair().public.to_bytes().to_vec().into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(3))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_signed_transfer() {
+        let (_, mut contract) = setup();
+        assert_eq!(contract.get_next_nonce(accounts(2)), 1);
+        let signature = sign(accounts(2), accounts(1), 100, 1);
+        contract.ft_transfer_with_signature(accounts(2), accounts(1), 100.into(), 1, signature);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.get_next_nonce(accounts(2)), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "The nonce should be 2")]
+    fn test_replayed_nonce() {
+        let (_, mut contract) = setup();
+        let signature = sign(accounts(2), accounts(1), 100, 1);
+        contract.ft_transfer_with_signature(
+            accounts(2),
+            accounts(1),
+            100.into(),
+            1,
+            signature.clone(),
+        );
+        contract.ft_transfer_with_signature(accounts(2), accounts(1), 100.into(), 1, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account has no signing key")]
+    fn test_closed_account_loses_signing_key() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.set_signing_key(keypair().public.to_bytes().to_vec().into());
+        let signature = sign(accounts(1), accounts(2), 0, 1);
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        assert!(contract.storage_unregister(None));
+        assert_eq!(contract.get_next_nonce(accounts(1)), 1);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .build());
+        contract.storage_deposit(None, None);
+        contract.ft_transfer_with_signature(accounts(1), accounts(2), 0.into(), 1, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The nonce should be 2")]
+    fn test_replay_after_reregistering() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.ft_transfer(accounts(1), 100.into(), None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.set_signing_key(keypair().public.to_bytes().to_vec().into());
+        let signature = sign(accounts(1), accounts(2), 100, 1);
+        contract.ft_transfer_with_signature(
+            accounts(1),
+            accounts(2),
+            100.into(),
+            1,
+            signature.clone(),
+        );
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        assert!(contract.storage_unregister(None));
+        assert_eq!(contract.get_next_nonce(accounts(1)), 2);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        contract.set_signing_key(keypair().public.to_bytes().to_vec().into());
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.ft_transfer(accounts(1), 100.into(), None);
+        contract.ft_transfer_with_signature(accounts(1), accounts(2), 100.into(), 1, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_tampered_amount() {
+        let (_, mut contract) = setup();
+        let signature = sign(accounts(2), accounts(1), 100, 1);
+        contract.ft_transfer_with_signature(accounts(2), accounts(1), 1_000.into(), 1, signature);
+    }
+}
@@ -0,0 +1,61 @@
+//! A compact view of the token metadata. `ft_metadata` returns the embedded icon on every call,
+//! which is most of the response, while most clients only need the name, symbol and decimals.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+/// The token metadata without the icon and the reference.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LiteMetadata {
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the token metadata without the icon and the reference. `ft_metadata` returns the
+    /// full metadata.
+    pub fn ft_metadata_lite(&self) -> LiteMetadata {
+        let metadata = self.metadata.get().unwrap();
+        LiteMetadata {
+            spec: metadata.spec,
+            name: metadata.name,
+            symbol: metadata.symbol,
+            decimals: metadata.decimals,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_ft_metadata_lite() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), 1_000.into(), None, None);
+        let metadata = contract.ft_metadata();
+        assert!(metadata.icon.is_some());
+
+        let lite = contract.ft_metadata_lite();
+        assert_eq!(
+            lite,
+            LiteMetadata {
+                spec: metadata.spec,
+                name: metadata.name,
+                symbol: metadata.symbol,
+                decimals: metadata.decimals,
+            }
+        );
+        let json = near_sdk::serde_json::to_string(&lite).unwrap();
+        assert!(!json.contains("icon"));
+        assert!(!json.contains("data:image"));
+    }
+}
@@ -0,0 +1,263 @@
+//! State migration from the original contract layout.
+//!
+//! New fields can't be deserialized from the state written by an older version of the contract,
+//! so upgrading an existing deployment requires calling `migrate` right after deploying the new
+//! code. Both actions should be sent as a single batch transaction (`DeployContract` followed by a
+//! `FunctionCall` to `migrate`), otherwise every other call in between fails on reading the state.
+//!
+//! The old state doesn't track the registered accounts, so the holders set starts empty and the
+//! owner has to import the accounts registered before the migration with `import_holders`, e.g.
+//! from an indexer, then call `finish_holders_import`. Snapshots and reward distributions are only
+//! available once the import is finished. The accounts registered before the migration paid the
+//! old storage minimum, which is what they get back when they unregister.
+use crate::*;
+
+/// The state layout of the contract before the admin features were added.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct OldContract {
+    pub(crate) token: FungibleToken,
+    pub(crate) metadata: LazyOption<FungibleTokenMetadata>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Migrates the state of a contract deployed before the admin features were added.
+    /// Must be called in the same transaction as the code deploy.
+    /// `owner_id` becomes the owner and the treasury, and is registered if it isn't yet.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: AccountId) -> Self {
+        let old: OldContract = env::state_read().expect("Failed to read the old state");
+        let total_supply = old.token.total_supply;
+        let legacy_account_storage_usage = old.token.account_storage_usage;
+        let mut this = Self {
+            token: old.token,
+            metadata: old.metadata,
+            owner_id: owner_id.clone(),
+            pause_flags: PauseFlags::default(),
+            blacklist: UnorderedSet::new(b"b".to_vec()),
+            max_supply: None,
+            holders_count: 0,
+            minters: UnorderedSet::new(b"n".to_vec()),
+            treasury_id: owner_id.clone(),
+            fee_basis_points: 0,
+            burn_basis_points: 0,
+            allowances: LookupMap::new(b"l".to_vec()),
+            locks: LookupMap::new(b"v".to_vec()),
+            holders: UnorderedSet::new(b"h".to_vec()),
+            snapshots: Vector::new(b"s".to_vec()),
+            snapshot_balances: LookupMap::new(b"t".to_vec()),
+            excluded_accounts: UnorderedSet::new(b"x".to_vec()),
+            locked_total: 0,
+            pending_owner: None,
+            min_transfer_amount: 0,
+            mint_cap_per_epoch: 0,
+            epoch_length_ns: 0,
+            current_epoch: 0,
+            minted_this_epoch: 0,
+            admin_actions: Vector::new(b"q".to_vec()),
+            max_memo_len: MAX_MEMO_LEN,
+            closed_accounts: UnorderedMap::new(b"c".to_vec()),
+            proposals: Vector::new(b"p".to_vec()),
+            registry_id: None,
+            wrappable: false,
+            daily_limit: None,
+            daily_transfers: LookupMap::new(b"d".to_vec()),
+            whitelist_mode: false,
+            whitelist: UnorderedSet::new(b"w".to_vec()),
+            supply_history: Vector::new(b"r".to_vec()),
+            supply_history_start: 0,
+            max_history_len: DEFAULT_MAX_HISTORY_LEN,
+            auto_pause_threshold_bps: None,
+            signing_keys: LookupMap::new(b"k".to_vec()),
+            nonces: LookupMap::new(b"o".to_vec()),
+            registration_premium: 0,
+            extra_metadata: UnorderedMap::new(b"e".to_vec()),
+            fee_token_id: None,
+            fee_flat_amount: 0,
+            transfer_cooldown_ns: 0,
+            last_transfers: LookupMap::new(b"f".to_vec()),
+            mint_deadline_ns: None,
+            frozen_senders: UnorderedSet::new(b"i".to_vec()),
+            rename_cooldown_ns: DEFAULT_RENAME_COOLDOWN_NS,
+            last_rename_ts: None,
+            guardian_id: None,
+            event_version: DEFAULT_EVENT_VERSION.to_string(),
+            burn_mode: BurnMode::ReduceSupply,
+            in_progress: LookupSet::new(b"ip".to_vec()),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            reward_distribution: None,
+            allow_auto_register: false,
+            total_minted: total_supply,
+            total_burned: 0,
+            require_memo: false,
+            metadata_authority_pk: None,
+            metadata_nonce: 0,
+            pending_transfers: LookupMap::new(b"j".to_vec()),
+            next_pending_id: 0,
+            pending_balances: LookupMap::new(b"u".to_vec()),
+            fee_round_up: false,
+            metadata_locked: false,
+            transfer_counts: LookupMap::new(b"y".to_vec()),
+            ft_on_transfer_gas: None,
+            transfer_call_allowlist: UnorderedSet::new(b"z".to_vec()),
+            emit_events: true,
+            fee_recipients: Vec::new(),
+            mint_keys: UnorderedSet::new(b"mk".to_vec()),
+            holders_indexed: false,
+            legacy_account_storage_usage: Some(legacy_account_storage_usage),
+            legacy_accounts: LookupSet::new(b"la".to_vec()),
+        };
+        this.measure_account_storage_usage();
+        if this.token.accounts.contains_key(&owner_id) {
+            this.import_holder(&owner_id);
+        } else {
+            this.internal_register_account(&owner_id);
+        }
+        this
+    }
+
+    /// Adds the accounts registered before the migration to the holders set. Unregistered
+    /// accounts and known holders are skipped. Returns the number of accounts added. The storage
+    /// of the added entries is paid by the contract. Can only be called by the owner.
+    pub fn import_holders(&mut self, account_ids: Vec<AccountId>) -> u64 {
+        self.assert_owner();
+        assert!(!self.holders_indexed, "The holders are already imported");
+        let mut imported = 0;
+        for account_id in account_ids.iter() {
+            if self.token.accounts.contains_key(account_id) && !self.holders.contains(account_id) {
+                self.import_holder(account_id);
+                imported += 1;
+            }
+        }
+        log!("{} holders imported by @{}", imported, self.owner_id);
+        imported
+    }
+
+    /// Marks the holders set as complete, which enables snapshots and reward distributions.
+    /// Can only be called by the owner.
+    pub fn finish_holders_import(&mut self) {
+        self.assert_owner();
+        self.holders_indexed = true;
+        log!("Holders import finished by @{}", self.owner_id);
+    }
+
+    /// Returns whether the holders set contains every registered account.
+    pub fn is_holders_index_complete(&self) -> bool {
+        self.holders_indexed
+    }
+}
+
+impl Contract {
+    fn import_holder(&mut self, account_id: &AccountId) {
+        self.holders.insert(account_id);
+        self.holders_count += 1;
+        self.legacy_accounts.insert(account_id);
+    }
+
+    /// Returns the old storage usage of an account if it was registered before the migration,
+    /// i.e. it was imported or isn't a holder yet.
+    pub(crate) fn legacy_storage_usage(&self, account_id: &AccountId) -> Option<StorageUsage> {
+        self.legacy_account_storage_usage.filter(|_| {
+            self.legacy_accounts.contains(account_id) || !self.holders.contains(account_id)
+        })
+    }
+
+    pub(crate) fn assert_holders_indexed(&self) {
+        assert!(
+            self.holders_indexed,
+            "The holders index is incomplete, finish the holders import first"
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+
+        let mut token = FungibleToken::new(b"a".to_vec());
+        let old_min_balance = Balance::from(token.account_storage_usage) * env::storage_byte_cost();
+        token.internal_register_account(&accounts(1));
+        token.internal_deposit(&accounts(1), 1_000);
+        let metadata = FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Old token".to_string(),
+            symbol: "OLD".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 8,
+        };
+        let old = OldContract { token, metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)) };
+        env::state_write(&old);
+
+        let mut contract = Contract::migrate(accounts(2));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+        assert_eq!(contract.ft_metadata().symbol, "OLD");
+        assert_eq!(contract.get_owner(), accounts(2));
+        assert_eq!(contract.get_treasury(), accounts(2));
+        assert!(!contract.is_paused());
+        assert!(!contract.is_blacklisted(accounts(1)));
+        assert_eq!(contract.get_max_supply(), None);
+
+        // Only the owner is a known holder until the old accounts are imported.
+        assert_eq!(contract.ft_holders_count(), 1);
+        assert!(!contract.is_holders_index_complete());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.import_holders(vec![accounts(1), accounts(2), accounts(3)]), 1);
+        contract.finish_holders_import();
+        assert!(contract.is_holders_index_complete());
+        assert_eq!(contract.ft_holders_count(), 2);
+
+        // The old accounts keep the storage balance they paid.
+        assert!(contract.storage_balance_bounds().min.0 > old_min_balance);
+        assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().total.0, old_min_balance);
+        assert_eq!(
+            contract.storage_balance_of(accounts(2)).unwrap().total,
+            contract.storage_balance_bounds().min
+        );
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.storage_unregister(Some(true));
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(contract.ft_holders_count(), 1);
+
+        // The closure record isn't refunded, and it takes more than the old storage minimum.
+        let storage_usage = env::storage_usage();
+        testing_env!(context
+            .storage_usage(storage_usage)
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.purge_closed_records(u64::MAX.into(), 0, 10);
+        let record_cost =
+            Balance::from(storage_usage - env::storage_usage()) * env::storage_byte_cost();
+        assert!(record_cost > old_min_balance);
+        assert!(format!("{:?}", receipts[0].actions).contains("Transfer { deposit: 1 }"));
+    }
+
+    #[test]
+    #[should_panic(expected = "The holders index is incomplete, finish the holders import first")]
+    fn test_snapshot_before_holders_import() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        let old = OldContract {
+            token: FungibleToken::new(b"a".to_vec()),
+            metadata: LazyOption::new(b"m".to_vec(), None),
+        };
+        env::state_write(&old);
+        let mut contract = Contract::migrate(accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.snapshot(0, 10);
+    }
+}
@@ -0,0 +1,112 @@
+//! Idempotent minting. A backend that retries a failed mint can't tell whether the first attempt
+//! went through, so `ft_mint_idempotent` takes a key that identifies the mint and skips keys it has
+//! seen before. The used keys stay in the state until the owner prunes them, and at most
+//! `MAX_MINT_KEYS` of them are kept.
+use crate::*;
+
+/// The longest mint key, in bytes.
+const MAX_MINT_KEY_LEN: usize = 64;
+/// The most mint keys kept at once.
+const MAX_MINT_KEYS: u64 = 10_000;
+
+#[near_bindgen]
+impl Contract {
+    /// Same as `ft_mint`, but mints only once per `key`. Returns whether the tokens were minted,
+    /// `false` if `key` was used before, in which case the attached deposit is refunded. Can only
+    /// be called by the owner or a minter.
+    #[payable]
+    pub fn ft_mint_idempotent(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        key: String,
+        memo: Option<String>,
+    ) -> bool {
+        self.assert_minter();
+        assert!(
+            !key.is_empty() && key.len() <= MAX_MINT_KEY_LEN,
+            "The mint key must have between 1 and {} bytes",
+            MAX_MINT_KEY_LEN
+        );
+        if self.mint_keys.contains(&key) {
+            log!("The mint key {} was used already, skipping the mint", key);
+            let deposit = env::attached_deposit();
+            if deposit > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(deposit);
+            }
+            return false;
+        }
+        assert!(
+            self.mint_keys.len() < MAX_MINT_KEYS,
+            "Too many mint keys are stored, the owner has to prune them"
+        );
+        self.mint_keys.insert(&key);
+        self.ft_mint(account_id, amount, memo);
+        true
+    }
+
+    /// Forgets the used mint `keys`, which frees their storage but lets them mint again. Only
+    /// keys that can't be retried anymore should be pruned. Can only be called by the owner.
+    pub fn prune_mint_keys(&mut self, keys: Vec<String>) {
+        self.assert_owner();
+        let pruned = keys.iter().filter(|key| self.mint_keys.remove(key)).count();
+        log!("{} mint keys pruned by @{}", pruned, self.owner_id);
+    }
+
+    /// Returns whether `key` was used by `ft_mint_idempotent`.
+    pub fn is_mint_key_used(&self, key: String) -> bool {
+        self.mint_keys.contains(&key)
+    }
+
+    /// Returns the number of stored mint keys.
+    pub fn mint_keys_count(&self) -> u64 {
+        self.mint_keys.len()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_repeated_key_mints_once() {
+        let (_, mut contract) = setup();
+        assert!(contract.ft_mint_idempotent(accounts(2), 1_000.into(), "a-1".to_string(), None));
+        assert!(!contract.ft_mint_idempotent(accounts(2), 1_000.into(), "a-1".to_string(), None));
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+        assert!(contract.is_mint_key_used("a-1".to_string()));
+
+        assert!(contract.ft_mint_idempotent(accounts(2), 1_000.into(), "a-2".to_string(), None));
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 2_000);
+        assert_eq!(contract.mint_keys_count(), 2);
+    }
+
+    #[test]
+    fn test_prune_mint_keys() {
+        let (_, mut contract) = setup();
+        contract.ft_mint_idempotent(accounts(2), 1_000.into(), "a-1".to_string(), None);
+        contract.prune_mint_keys(vec!["a-1".to_string(), "unknown".to_string()]);
+        assert_eq!(contract.mint_keys_count(), 0);
+        assert!(!contract.is_mint_key_used("a-1".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "The mint key must have between 1 and 64 bytes")]
+    fn test_empty_mint_key() {
+        let (_, mut contract) = setup();
+        contract.ft_mint_idempotent(accounts(2), 1_000.into(), String::new(), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! Pausing of single operations. `pause` and `unpause` switch everything at once, while
+//! `set_pause_flags` freezes transfers, mints and burns independently, e.g. to stop minting
+//! during an incident but keep transfers live.
+//!
+//! Besides the owner, an optional guardian can pause operations. The guardian is an emergency
+//! stop only: it can't unpause, mint or change the ownership. Pausing and unpausing take effect
+//! right away, the owner can also schedule them through the timelock, see `AdminAction`.
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseFlags {
+    pub transfers: bool,
+    pub mints: bool,
+    pub burns: bool,
+}
+
+impl PauseFlags {
+    /// Pauses every operation.
+    pub const ALL: PauseFlags = PauseFlags { transfers: true, mints: true, burns: true };
+
+    /// Returns the flags paused in either `self` or `other`.
+    fn union(self, other: PauseFlags) -> PauseFlags {
+        PauseFlags {
+            transfers: self.transfers || other.transfers,
+            mints: self.mints || other.mints,
+            burns: self.burns || other.burns,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pauses and resumes transfers, mints and burns independently. Can only be called by the
+    /// owner or the guardian. The guardian can only pause more operations: the flags it sets are
+    /// added to the current ones, and only the owner can clear a flag.
+    pub fn set_pause_flags(&mut self, flags: PauseFlags) {
+        self.assert_owner_or_guardian();
+        let flags = if env::predecessor_account_id() == self.owner_id {
+            flags
+        } else {
+            self.pause_flags.union(flags)
+        };
+        self.internal_set_pause_flags(flags);
+    }
+
+    /// Sets the guardian that can pause the contract, `None` removes it.
+    /// Can only be called by the owner.
+    pub fn set_guardian(&mut self, guardian_id: Option<AccountId>) {
+        self.assert_owner();
+        log!("Guardian set to {:?} by @{}", guardian_id, self.owner_id);
+        self.guardian_id = guardian_id;
+    }
+
+    /// Returns the guardian that can pause the contract.
+    pub fn get_guardian(&self) -> Option<AccountId> {
+        self.guardian_id.clone()
+    }
+
+    /// Returns which operations are paused.
+    pub fn pause_flags(&self) -> PauseFlags {
+        self.pause_flags
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner_or_guardian(&self) {
+        if Some(&env::predecessor_account_id()) != self.guardian_id.as_ref() {
+            self.assert_owner();
+        }
+    }
+
+    pub(crate) fn internal_set_pause_flags(&mut self, flags: PauseFlags) {
+        self.pause_flags = flags;
+        log!("Pause flags set to {:?} by @{}", flags, env::predecessor_account_id());
+    }
+
+    pub(crate) fn internal_pause(&mut self) {
+        self.pause_flags = PauseFlags::ALL;
+        log!("Contract paused by @{}", env::predecessor_account_id());
+    }
+
+    pub(crate) fn internal_unpause(&mut self) {
+        self.pause_flags = PauseFlags::default();
+        log!("Contract unpaused by @{}", self.owner_id);
+    }
+
+    pub(crate) fn assert_mints_not_paused(&self) {
+        require(!self.pause_flags.mints, ContractError::MintsPaused);
+        self.assert_no_snapshot_in_progress();
+    }
+
+    pub(crate) fn assert_burns_not_paused(&self) {
+        require(!self.pause_flags.burns, ContractError::BurnsPaused);
+        self.assert_no_snapshot_in_progress();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_while_mints_paused() {
+        let (_, mut contract) = setup();
+        let flags = PauseFlags { mints: true, ..Default::default() };
+        contract.set_pause_flags(flags);
+        assert_eq!(contract.pause_flags(), flags);
+        assert!(!contract.is_paused());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        contract.ft_burn(1_000.into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED: Minting is paused")]
+    fn test_mint_while_mints_paused() {
+        let (_, mut contract) = setup();
+        contract.set_pause_flags(PauseFlags { mints: true, ..Default::default() });
+        contract.ft_mint(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED: Burning is paused")]
+    fn test_burn_while_burns_paused() {
+        let (_, mut contract) = setup();
+        contract.set_pause_flags(PauseFlags { burns: true, ..Default::default() });
+        contract.ft_burn(1_000.into(), None);
+    }
+
+    #[test]
+    fn test_pause_sets_all_flags() {
+        let (_, mut contract) = setup();
+        contract.pause();
+        assert_eq!(contract.pause_flags(), PauseFlags::ALL);
+        contract.unpause();
+        assert_eq!(contract.pause_flags(), PauseFlags::default());
+    }
+
+    #[test]
+    fn test_owner_clears_pause_flags() {
+        let (_, mut contract) = setup();
+        contract.pause();
+        contract.set_pause_flags(PauseFlags { mints: true, ..Default::default() });
+        assert_eq!(contract.pause_flags(), PauseFlags { mints: true, ..Default::default() });
+    }
+
+    #[test]
+    fn test_timelock_sets_pause_flags() {
+        let (mut context, mut contract) = setup();
+        let flags = PauseFlags { burns: true, ..Default::default() };
+        let execute_after_ns = env::block_timestamp() + crate::timelock::MIN_ACTION_DELAY_NS;
+        let index =
+            contract.queue_action(AdminAction::SetPauseFlags { flags }, execute_after_ns.into());
+        testing_env!(context.block_timestamp(execute_after_ns).build());
+        contract.execute_action(index);
+        assert_eq!(contract.pause_flags(), flags);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_set_pause_flags_by_stranger() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_pause_flags(PauseFlags::ALL);
+    }
+
+    #[test]
+    fn test_guardian_can_pause() {
+        let (mut context, mut contract) = setup();
+        contract.set_guardian(Some(accounts(3)));
+        assert_eq!(contract.get_guardian(), Some(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.pause();
+        assert_eq!(contract.pause_flags(), PauseFlags::ALL);
+        contract.set_pause_flags(PauseFlags { mints: true, ..Default::default() });
+        assert_eq!(contract.pause_flags(), PauseFlags::ALL);
+    }
+
+    #[test]
+    fn test_guardian_pause_flags_add_up() {
+        let (mut context, mut contract) = setup();
+        contract.set_pause_flags(PauseFlags { burns: true, ..Default::default() });
+        contract.set_guardian(Some(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.set_pause_flags(PauseFlags { mints: true, ..Default::default() });
+        assert_eq!(
+            contract.pause_flags(),
+            PauseFlags { transfers: false, mints: true, burns: true }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_MINTER: Only the owner or a minter can call this method")]
+    fn test_guardian_cannot_mint() {
+        let (mut context, mut contract) = setup();
+        contract.set_guardian(Some(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(1), 1_000.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_guardian_cannot_unpause() {
+        let (mut context, mut contract) = setup();
+        contract.set_guardian(Some(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.pause();
+        contract.unpause();
+    }
+}
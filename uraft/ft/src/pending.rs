@@ -0,0 +1,282 @@
+//! Transfers the receiver has to claim. `create_pending_transfer` reserves tokens of the sender
+//! for the receiver, who can claim them until the expiry. Past the expiry, the sender can reclaim
+//! them instead. Reserved tokens stay in the balance of the sender, but can't be spent until the
+//! pending transfer is claimed or reclaimed, and the sender can't unregister until then.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingTransfer {
+    pub sender_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    /// The tokens of the sender reserved for the transfer: the amount, and the fee and the burn
+    /// charged on top of it when the transfer is created.
+    pub reserved: U128,
+    /// The block timestamp from which the receiver can't claim the tokens anymore.
+    pub expiry_ns: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Reserves `amount` tokens of the predecessor for `to`, who can claim them with
+    /// `claim_pending` until `expiry_ns`. Returns the id of the pending transfer. If it trips the
+    /// circuit breaker, nothing is reserved, the deposit is refunded and the call fails like
+    /// `ft_transfer` does. The transfer goes through the same checks as `ft_transfer` when it's
+    /// created and counts towards the daily limit and the cooldown of the sender. The fee and the
+    /// burn are charged when the tokens move, once the transfer is claimed, but they are reserved
+    /// on top of `amount` right away. The attached deposit has to cover the storage of the pending
+    /// transfer, the rest is refunded. The storage is refunded to the sender once the transfer is
+    /// claimed or reclaimed.
+    #[payable]
+    pub fn create_pending_transfer(
+        &mut self,
+        to: AccountId,
+        amount: U128,
+        expiry_ns: U64,
+    ) -> PromiseOrValue<u64> {
+        let sender_id = env::predecessor_account_id();
+        assert!(expiry_ns.0 > env::block_timestamp(), "The expiry should be in the future");
+        require(sender_id != to, ContractError::SameAccount);
+        require(amount.0 > 0, ContractError::ZeroAmount);
+        if let Some(rejection) = self.trip_circuit_breaker(&sender_id, &to, amount.into()) {
+            if env::attached_deposit() > 0 {
+                Promise::new(sender_id).transfer(env::attached_deposit());
+            }
+            return PromiseOrValue::Promise(rejection);
+        }
+        let reserved = self
+            .transfer_debit(&sender_id, &to, amount.into())
+            .unwrap_or_else(|error| error.panic());
+        self.assert_spendable(&sender_id, reserved);
+        self.internal_record_daily_transfer(&sender_id, amount.into());
+        self.internal_record_transfer_time(&sender_id);
+        self.internal_count_transfer(&sender_id);
+
+        let initial_storage_usage = env::storage_usage();
+        let id = self.next_pending_id;
+        self.next_pending_id += 1;
+        let pending_balance = self.internal_pending_balance_of(&sender_id) + reserved;
+        self.pending_balances.insert(&sender_id, &pending_balance);
+        self.pending_transfers.insert(
+            &id,
+            &PendingTransfer {
+                sender_id: sender_id.clone(),
+                receiver_id: to,
+                amount,
+                reserved: reserved.into(),
+                expiry_ns,
+            },
+        );
+        settle_storage_deposit(initial_storage_usage);
+        log!("@{} created the pending transfer {} of {}", sender_id, id, amount.0);
+        PromiseOrValue::Value(id)
+    }
+
+    /// Transfers the tokens of the pending transfer `id` to the predecessor, its receiver. The fee
+    /// and the burn are charged to the sender on top of them, at the rates of the claim, so the
+    /// claim fails if the rates went up and the sender can't cover them. Only possible before the
+    /// expiry and if the transfer still passes the checks of the transfer hooks, e.g. the receiver
+    /// isn't blacklisted. Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn claim_pending(&mut self, id: u64) {
+        assert_one_yocto();
+        let pending = self.internal_remove_pending(id);
+        assert!(
+            pending.receiver_id == env::predecessor_account_id(),
+            "Only the receiver can claim the pending transfer"
+        );
+        assert!(env::block_timestamp() < pending.expiry_ns.0, "The pending transfer has expired");
+        self.assert_transfer_allowed(
+            &pending.sender_id,
+            &pending.receiver_id,
+            pending.amount.into(),
+        );
+        self.internal_deduct_and_move(
+            &pending.sender_id,
+            &pending.receiver_id,
+            pending.amount.into(),
+            Some("claim"),
+        );
+    }
+
+    /// Releases the tokens of the pending transfer `id` back to the predecessor, its sender.
+    /// Only possible from the expiry. Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn reclaim_pending(&mut self, id: u64) {
+        assert_one_yocto();
+        let pending = self.internal_remove_pending(id);
+        assert!(
+            pending.sender_id == env::predecessor_account_id(),
+            "Only the sender can reclaim the pending transfer"
+        );
+        assert!(
+            env::block_timestamp() >= pending.expiry_ns.0,
+            "The pending transfer can be reclaimed from {}",
+            pending.expiry_ns.0
+        );
+        log!("@{} reclaimed the pending transfer {}", pending.sender_id, id);
+    }
+
+    /// Returns the pending transfer `id`, or `None` if it doesn't exist or was settled.
+    pub fn get_pending_transfer(&self, id: u64) -> Option<PendingTransfer> {
+        self.pending_transfers.get(&id)
+    }
+
+    /// Returns the amount of tokens of `account_id` reserved by its pending transfers.
+    pub fn pending_balance_of(&self, account_id: AccountId) -> U128 {
+        self.internal_pending_balance_of(&account_id).into()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_pending_balance_of(&self, account_id: &AccountId) -> Balance {
+        self.pending_balances.get(account_id).unwrap_or(0)
+    }
+
+    /// Removes the pending transfer `id`, releases its reservation and refunds its storage to
+    /// the sender.
+    fn internal_remove_pending(&mut self, id: u64) -> PendingTransfer {
+        let initial_storage_usage = env::storage_usage();
+        let pending =
+            self.pending_transfers.remove(&id).expect("The pending transfer doesn't exist");
+        let pending_balance =
+            self.internal_pending_balance_of(&pending.sender_id) - pending.reserved.0;
+        if pending_balance == 0 {
+            self.pending_balances.remove(&pending.sender_id);
+        } else {
+            self.pending_balances.insert(&pending.sender_id, &pending_balance);
+        }
+        let released = initial_storage_usage.saturating_sub(env::storage_usage());
+        let refund = Balance::from(released) * env::storage_byte_cost();
+        if refund > 0 {
+            Promise::new(pending.sender_id.clone()).transfer(refund);
+        }
+        pending
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+    const EXPIRY: u64 = 1_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        let id = contract.create_pending_transfer(accounts(1), 1_000.into(), EXPIRY.into());
+        assert!(matches!(id, PromiseOrValue::Value(0)));
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_claim_pending() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.pending_balance_of(accounts(2)).0, 1_000);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.claim_pending(0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.pending_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.get_pending_transfer(0), None);
+    }
+
+    #[test]
+    fn test_reclaim_expired_pending() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.block_timestamp(EXPIRY).build());
+        contract.reclaim_pending(0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY);
+        assert_eq!(contract.pending_balance_of(accounts(2)).0, 0);
+        contract.ft_transfer(accounts(1), TOTAL_SUPPLY.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The pending transfer can be reclaimed from 1000")]
+    fn test_reclaim_pending_early() {
+        let (_, mut contract) = setup();
+        contract.reclaim_pending(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LOCKED_BALANCE")]
+    fn test_spend_pending_tokens() {
+        let (_, mut contract) = setup();
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY - 999).into(), None);
+    }
+
+    #[test]
+    fn test_claim_pending_burns() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(0).build());
+        contract.set_burn_basis_points(100);
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.claim_pending(0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 1_010);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 10);
+    }
+
+    #[test]
+    fn test_pending_transfer_reserves_burn() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(0).build());
+        contract.set_burn_basis_points(100);
+        testing_env!(context.attached_deposit(env::storage_byte_cost() * 1_000).build());
+        contract.create_pending_transfer(accounts(1), 1_000.into(), EXPIRY.into());
+        assert_eq!(contract.get_pending_transfer(1).unwrap().reserved.0, 1_010);
+        assert_eq!(contract.pending_balance_of(accounts(2)).0, 2_010);
+
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.claim_pending(1);
+        assert_eq!(contract.pending_balance_of(accounts(2)).0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED: Receiver is blacklisted")]
+    fn test_claim_pending_blacklisted_receiver() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(0).build());
+        contract.blacklist_add(accounts(1));
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.claim_pending(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DAILY_LIMIT_EXCEEDED")]
+    fn test_pending_transfers_count_toward_daily_limit() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(0).build());
+        contract.set_daily_limit(Some(1_500.into()));
+        testing_env!(context.attached_deposit(env::storage_byte_cost() * 1_000).build());
+        contract.create_pending_transfer(accounts(1), 1_000.into(), EXPIRY.into());
+        contract.create_pending_transfer(accounts(1), 1_000.into(), EXPIRY.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "The account can't be unregistered while it has pending transfers")]
+    fn test_force_unregister_with_pending_transfers() {
+        let (_, mut contract) = setup();
+        contract.storage_unregister(Some(true));
+    }
+}
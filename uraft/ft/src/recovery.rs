@@ -0,0 +1,82 @@
+//! Owner-only recovery of tokens stuck on accounts that can't return them.
+//! Only compiled with the `admin_recovery` feature.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Moves `amount` tokens from `from` to `to` without the consent of `from`. Can only be called
+    /// by the owner. `to` has to be registered. Pause, blacklist, locks and fees are not applied.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn admin_force_transfer(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(self.token.accounts.contains_key(&to), "The account {} is not registered", to);
+        let memo = match memo {
+            Some(memo) => format!("Admin force transfer: {}", memo),
+            None => "Admin force transfer".to_string(),
+        };
+        self.internal_move_tokens(&from, &to, amount.into(), Some(&memo));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer(accounts(1), 1_000.into(), None);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_admin_force_transfer() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.admin_force_transfer(accounts(1), accounts(2), 400.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 600);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 600);
+        assert!(get_logs()[0].contains(r#""memo":"Admin force transfer""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_admin_force_transfer_not_owner() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.admin_force_transfer(accounts(2), accounts(1), 400.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_admin_force_transfer_unregistered() {
+        let (_, mut contract) = setup();
+        contract.admin_force_transfer(accounts(1), accounts(3), 400.into(), None);
+    }
+}
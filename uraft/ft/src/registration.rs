@@ -0,0 +1,113 @@
+//! Registration that reports its outcome instead of panicking, so batch flows can register many
+//! accounts and handle the short deposits themselves.
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// The outcome of `try_storage_deposit`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RegistrationResult {
+    /// The account has been registered.
+    Registered,
+    /// The account was registered already, the deposit has been refunded.
+    AlreadyRegistered,
+    /// The deposit doesn't cover the `required` amount, it has been refunded.
+    InsufficientDeposit { required: U128 },
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Same as `storage_deposit`, but returns `InsufficientDeposit` and refunds the whole
+    /// attached deposit when it doesn't cover the minimum storage balance and the registration
+    /// premium, instead of panicking. `account_id` defaults to the predecessor.
+    #[payable]
+    pub fn try_storage_deposit(&mut self, account_id: Option<AccountId>) -> RegistrationResult {
+        let deposit = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let required = self.token.storage_balance_bounds().min.0 + self.registration_premium;
+        let (result, refund) = if self.token.accounts.contains_key(&account_id) {
+            (RegistrationResult::AlreadyRegistered, deposit)
+        } else if deposit < required {
+            (RegistrationResult::InsufficientDeposit { required: required.into() }, deposit)
+        } else {
+            let refund = self.internal_register_with_deposit(&account_id, deposit);
+            (RegistrationResult::Registered, refund)
+        };
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        result
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), 1_000_000.into(), None, None);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_try_storage_deposit_registers() {
+        let (mut context, mut contract) = setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(min_balance + 10)
+            .predecessor_account_id(accounts(1))
+            .build());
+        assert_eq!(contract.try_storage_deposit(None), RegistrationResult::Registered);
+        assert!(contract.is_registered(accounts(1)));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert!(format!("{:?}", receipts[0].actions).contains("deposit: 10 "));
+    }
+
+    #[test]
+    fn test_try_storage_deposit_already_registered() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.attached_deposit(100).build());
+        assert_eq!(
+            contract.try_storage_deposit(Some(accounts(2))),
+            RegistrationResult::AlreadyRegistered
+        );
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(2));
+        assert!(format!("{:?}", receipts[0].actions).contains("deposit: 100 "));
+    }
+
+    #[test]
+    fn test_try_storage_deposit_insufficient_deposit() {
+        let (mut context, mut contract) = setup();
+        contract.set_registration_premium(1_000.into());
+        let required = contract.storage_balance_bounds().min.0 + 1_000;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(required - 1)
+            .build());
+        assert_eq!(
+            contract.try_storage_deposit(Some(accounts(1))),
+            RegistrationResult::InsufficientDeposit { required: required.into() }
+        );
+        assert!(!contract.is_registered(accounts(1)));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(2));
+        assert!(
+            format!("{:?}", receipts[0].actions).contains(&format!("deposit: {} ", required - 1))
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Rewards minted to all holders in proportion to their balances in a finalized snapshot. A
+//! distribution covers the holders page by page: each call of `distribute_rewards` continues
+//! where the previous one stopped, so every holder is paid once. The shares are computed against
+//! the total supply of the snapshot, so neither the rewards minted by earlier pages nor the tokens
+//! moved between pages change later shares, and the rounding dust is minted to the owner once the
+//! last page is done. Accounts can't be unregistered during a distribution, which would reorder
+//! the holders that are left to pay.
+//!
+//! The pause flags, the mint deadline and the max supply are checked when a distribution starts,
+//! so a started distribution can always finish. The part of the reward that isn't minted yet is
+//! reserved against the max supply, and the owner can cancel a distribution with
+//! `cancel_distribution`.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+/// A distribution in progress.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardDistribution {
+    /// The snapshot whose balances the reward is distributed over.
+    pub snapshot_id: u64,
+    /// The amount distributed to all holders.
+    pub total_reward: U128,
+    /// The total supply of the snapshot.
+    pub total_supply: U128,
+    /// The index of the next holder to pay.
+    pub next_index: u64,
+    /// The amount minted so far.
+    pub distributed: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Mints to up to `limit` holders starting at `from_index` their share of `total_reward`,
+    /// `total_reward * balance / total_supply` with the balance and the total supply of the
+    /// finalized snapshot `snapshot_id`. The first call starts a distribution at index 0, the next
+    /// ones have to continue at the returned index with the same snapshot and `total_reward`.
+    /// Returns `None` once every holder is paid, after the rounding dust is minted to the owner.
+    /// Can only be called by the owner or a minter. Pages can't be distributed while a snapshot
+    /// is recorded.
+    pub fn distribute_rewards(
+        &mut self,
+        snapshot_id: u64,
+        total_reward: U128,
+        from_index: u64,
+        limit: u64,
+    ) -> Option<u64> {
+        self.assert_minter();
+        self.assert_not_wrappable();
+        self.assert_no_snapshot_in_progress();
+        let mut distribution = match self.reward_distribution.take() {
+            Some(distribution) => {
+                assert!(
+                    distribution.snapshot_id == snapshot_id
+                        && distribution.total_reward == total_reward,
+                    "A distribution of another reward is in progress"
+                );
+                distribution
+            }
+            None => self.internal_start_distribution(snapshot_id, total_reward.into()),
+        };
+        assert_eq!(
+            from_index, distribution.next_index,
+            "The distribution continues at index {}",
+            distribution.next_index
+        );
+
+        let holders = self.holders.as_vector();
+        let end = std::cmp::min(from_index.saturating_add(limit), holders.len());
+        let mut changes = BalanceChanges::default();
+        let mut minted: Balance = 0;
+        for index in from_index..end {
+            let account_id = holders.get(index).unwrap();
+            let balance =
+                self.snapshot_balances.get(&(snapshot_id, account_id.clone())).unwrap_or(0);
+            let share = mul_div(balance, total_reward.0, distribution.total_supply.0);
+            if share > 0 {
+                self.token.internal_deposit(&account_id, share);
+                changes.add(&account_id, share);
+                minted += share;
+            }
+        }
+        distribution.next_index = end;
+        distribution.distributed.0 += minted;
+
+        let finished = end == holders.len();
+        if finished {
+            let dust = distribution.total_reward.0 - distribution.distributed.0;
+            if dust > 0 {
+                self.token.internal_deposit(&self.owner_id, dust);
+                changes.add(&self.owner_id, dust);
+                minted += dust;
+            }
+            log!("Distributed a reward of {}", total_reward.0);
+        } else {
+            self.reward_distribution = Some(distribution);
+        }
+        if minted > 0 {
+            self.total_minted += minted;
+            if self.emit_events {
+                changes.emit_mint(&self.event_version, Some("reward"));
+            }
+            self.internal_record_supply();
+            self.notify_supply_change();
+        }
+        if finished {
+            None
+        } else {
+            Some(end)
+        }
+    }
+
+    /// Stops the distribution in progress and releases the part of the reward that isn't minted
+    /// yet. The shares minted so far are kept. Can only be called by the owner.
+    pub fn cancel_distribution(&mut self) {
+        self.assert_owner();
+        let distribution =
+            self.reward_distribution.take().expect("No reward distribution is in progress");
+        log!(
+            "Reward distribution cancelled after minting {} of {} by @{}",
+            distribution.distributed.0,
+            distribution.total_reward.0,
+            self.owner_id
+        );
+    }
+
+    /// Returns the distribution in progress, if any.
+    pub fn get_reward_distribution(&self) -> Option<RewardDistribution> {
+        self.reward_distribution.clone()
+    }
+}
+
+impl Contract {
+    /// Checks that `total_reward` can be minted and starts its distribution over the snapshot.
+    fn internal_start_distribution(
+        &mut self,
+        snapshot_id: u64,
+        total_reward: Balance,
+    ) -> RewardDistribution {
+        self.assert_mints_not_paused();
+        self.assert_before_mint_deadline();
+        require(total_reward > 0, ContractError::ZeroAmount);
+        let snapshot = self.snapshots.get(snapshot_id).expect("Snapshot not found");
+        assert!(snapshot.finalized, "The snapshot is not finalized");
+        assert!(snapshot.total_supply.0 > 0, "The snapshot has no supply to distribute over");
+        self.assert_within_max_supply(total_reward);
+        self.internal_record_mint(total_reward);
+        RewardDistribution {
+            snapshot_id,
+            total_reward: total_reward.into(),
+            total_supply: snapshot.total_supply,
+            next_index: 0,
+            distributed: 0.into(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        setup_with_max_supply(None)
+    }
+
+    fn setup_with_max_supply(max_supply: Option<U128>) -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract =
+            Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), max_supply, None);
+        for account_id in [accounts(1), accounts(3)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .build());
+            contract.storage_deposit(Some(account_id), None);
+        }
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 200_000.into(), None);
+        contract.ft_transfer(accounts(3), 300_000.into(), None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        let snapshot_id = contract.snapshot(0, 10);
+        contract.finalize_snapshot(snapshot_id);
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(0).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_distribute_rewards() {
+        let (_, mut contract) = setup();
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 0, 2), Some(2));
+        assert_eq!(contract.get_reward_distribution().unwrap().distributed.0, 700);
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 2, 2), None);
+        assert_eq!(contract.get_reward_distribution(), None);
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 200_200);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 300_300);
+        // The owner gets its share of 500 and the dust of 1.
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 500_501);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "The distribution continues at index 2")]
+    fn test_distribute_rewards_twice() {
+        let (_, mut contract) = setup();
+        contract.distribute_rewards(0, 1_000.into(), 0, 2);
+        contract.distribute_rewards(0, 1_000.into(), 0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MAX_SUPPLY_EXCEEDED")]
+    fn test_mint_between_pages_reserves_reward() {
+        let (_, mut contract) = setup_with_max_supply(Some((TOTAL_SUPPLY + 1_500).into()));
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 0, 2), Some(2));
+        // 800 can still be minted, but 301 of them are reserved for the last page.
+        contract.ft_mint(accounts(1), 500.into(), None);
+    }
+
+    #[test]
+    fn test_finish_distribution_after_mints_paused() {
+        let (_, mut contract) = setup();
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 0, 2), Some(2));
+        contract.set_pause_flags(PauseFlags { mints: true, ..Default::default() });
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 2, 2), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_001);
+    }
+
+    #[test]
+    fn test_cancel_distribution() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 0, 2), Some(2));
+        contract.cancel_distribution();
+        assert_eq!(contract.get_reward_distribution(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 700);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        assert!(contract.storage_unregister(Some(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot has no supply to distribute over")]
+    fn test_distribute_rewards_over_empty_snapshot() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(2), 1_000.into(), None, None);
+        contract.ft_burn(1_000.into(), None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        let snapshot_id = contract.snapshot(0, 10);
+        contract.finalize_snapshot(snapshot_id);
+        contract.distribute_rewards(snapshot_id, 1_000.into(), 0, 10);
+    }
+
+    #[test]
+    fn test_transfer_between_pages() {
+        let (mut context, mut contract) = setup();
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 0, 2), Some(2));
+
+        // The tokens moved to the unpaid holder don't increase its share.
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(3), 100_000.into(), None);
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(0).build());
+        assert_eq!(contract.distribute_rewards(0, 1_001.into(), 2, 2), None);
+
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 400_300);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 400_501);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SNAPSHOT_IN_PROGRESS")]
+    fn test_distribute_rewards_during_snapshot() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        let snapshot_id = contract.snapshot(0, 1);
+        contract.distribute_rewards(snapshot_id, 1_000.into(), 0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account can't be unregistered while rewards are distributed")]
+    fn test_unregister_during_distribution() {
+        let (mut context, mut contract) = setup();
+        contract.distribute_rewards(0, 1_000.into(), 0, 1);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.storage_unregister(Some(true));
+    }
+}
@@ -0,0 +1,105 @@
+//! Metadata updates authorized by an off-chain authority. The owner sets the ed25519 key of the
+//! authority, after which anyone can submit metadata the authority signed. Every update carries a
+//! nonce above the one of the previous update, so a signature can't be replayed.
+//!
+//! The signed message is the Borsh serialization of `(metadata, nonce: u64)`. It doesn't name the
+//! contract, so an authority key shouldn't be shared between tokens.
+use crate::meta::ed25519_verify;
+use crate::*;
+use std::convert::TryInto;
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the raw 32 byte ed25519 key of the metadata authority, or removes the authority if
+    /// `public_key` is `None`. Can only be called by the owner.
+    pub fn set_metadata_authority(&mut self, public_key: Option<Base64VecU8>) {
+        self.assert_owner();
+        self.metadata_authority_pk = public_key.map(|public_key| {
+            public_key.0.as_slice().try_into().expect("The public key should be 32 bytes long")
+        });
+        log!("Metadata authority set by @{}", self.owner_id);
+    }
+
+    /// Returns the key of the metadata authority, if any.
+    pub fn get_metadata_authority(&self) -> Option<Base64VecU8> {
+        self.metadata_authority_pk.map(|public_key| public_key.to_vec().into())
+    }
+
+    /// Returns the nonce of the latest signed metadata update, 0 if there was none.
+    pub fn get_metadata_nonce(&self) -> u64 {
+        self.metadata_nonce
+    }
+
+    /// Replaces the token metadata with `metadata` signed by the metadata authority. `nonce` has
+    /// to be above the nonce of the previous update. Can be called by anyone. `decimals` can't be
+    /// changed, like with `set_metadata`.
+    pub fn set_metadata_signed(
+        &mut self,
+        metadata: FungibleTokenMetadata,
+        nonce: u64,
+        signature: Base64VecU8,
+    ) {
+        let public_key = self.metadata_authority_pk.expect("There's no metadata authority");
+        assert!(nonce > self.metadata_nonce, "The nonce should be above {}", self.metadata_nonce);
+        let message = (&metadata, nonce).try_to_vec().unwrap();
+        assert!(ed25519_verify(&signature.0, &message, &public_key), "Invalid signature");
+        self.metadata_nonce = nonce;
+        self.internal_set_metadata(metadata);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[9; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn sign(metadata: &FungibleTokenMetadata, nonce: u64) -> Base64VecU8 {
+        let message = (metadata, nonce).try_to_vec().unwrap();
+        keypair().sign(&message).to_bytes().to_vec().into()
+    }
+
+    fn setup() -> (FungibleTokenMetadata, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), 1_000.into(), None, None);
+        contract.set_metadata_authority(Some(keypair().public.to_bytes().to_vec().into()));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let mut metadata = contract.ft_metadata();
+        metadata.name = "Renamed token".to_string();
+        (metadata, contract)
+    }
+
+    #[test]
+    fn test_set_metadata_signed() {
+        let (metadata, mut contract) = setup();
+        contract.set_metadata_signed(metadata.clone(), 1, sign(&metadata, 1));
+        assert_eq!(contract.ft_metadata().name, "Renamed token");
+        assert_eq!(contract.get_metadata_nonce(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_set_metadata_with_bad_signature() {
+        let (metadata, mut contract) = setup();
+        let signature = sign(&metadata, 1);
+        contract.set_metadata_signed(metadata, 2, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The nonce should be above 1")]
+    fn test_set_metadata_with_replayed_nonce() {
+        let (metadata, mut contract) = setup();
+        contract.set_metadata_signed(metadata.clone(), 1, sign(&metadata, 1));
+        contract.set_metadata_signed(metadata.clone(), 1, sign(&metadata, 1));
+    }
+}
@@ -0,0 +1,169 @@
+//! Dry runs and receipts of transfers. `simulate_transfer` goes through the same checks as
+//! `ft_transfer` without changing any state, so wallets can show the outcome of a transfer before
+//! sending it. `ft_transfer_detailed` is an `ft_transfer` that returns the fee it applied, and
+//! `max_transferable` tells how much can be sent at most.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+/// The outcome of a simulated transfer.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferPreview {
+    pub would_succeed: bool,
+    /// The fee that goes to the treasury, charged on top of the amount.
+    pub fee: U128,
+    /// The part that is burned, charged on top of the amount.
+    pub burn: U128,
+    /// The amount credited to the receiver.
+    pub net_received: U128,
+    /// The balance of the sender after the transfer.
+    pub from_balance_after: U128,
+    /// Why the transfer would fail, `None` if it would succeed.
+    pub reason: Option<String>,
+}
+
+/// The amounts applied by a transfer.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferReceipt {
+    /// The amount credited to the receiver.
+    pub net: U128,
+    /// The fee that went to the treasury, charged on top of the amount.
+    pub fee: U128,
+    /// The part that was burned, charged on top of the amount.
+    pub burn: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns what a transfer of `amount` from `from` to `to` would do right now.
+    pub fn simulate_transfer(
+        &self,
+        from: AccountId,
+        to: AccountId,
+        amount: U128,
+    ) -> TransferPreview {
+        let balance = self.token.accounts.get(&from).unwrap_or(0);
+        match self.check_transfer(&from, &to, amount.0) {
+            Ok((fee, burn)) => TransferPreview {
+                would_succeed: true,
+                fee: fee.into(),
+                burn: burn.into(),
+                net_received: amount,
+                from_balance_after: (balance - amount.0 - fee - burn).into(),
+                reason: None,
+            },
+            Err(reason) => TransferPreview {
+                would_succeed: false,
+                fee: 0.into(),
+                burn: 0.into(),
+                net_received: 0.into(),
+                from_balance_after: balance.into(),
+                reason: Some(reason),
+            },
+        }
+    }
+
+    /// Same as `ft_transfer`, but returns the amounts that were applied. If the transfer trips
+    /// the circuit breaker, it fails like `ft_transfer` does. Not available
+    /// while a fee token is set, since the transfer is then only completed in a callback.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn ft_transfer_detailed(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) -> PromiseOrValue<TransferReceipt> {
+        require(env::attached_deposit() == 1, ContractError::OneYocto);
+        assert!(
+            self.fee_token_id.is_none(),
+            "Detailed transfers aren't available while transfers pay a fee in another token"
+        );
+        let sender_id = env::predecessor_account_id();
+        if let Some(rejection) = self.trip_circuit_breaker(&sender_id, &receiver_id, amount.into())
+        {
+            return PromiseOrValue::Promise(rejection);
+        }
+        let (fee, burn) = self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+        PromiseOrValue::Value(TransferReceipt { net: amount, fee: fee.into(), burn: burn.into() })
+    }
+
+    /// Returns the largest amount `account_id` can transfer right now: the largest amount that,
+    /// with the fee and the burned part charged on top of it, fits its spendable balance, capped by
+    /// what's left of its daily limit and by the auto-pause threshold. It's 0 while the account
+    /// can't send at all, e.g. when it's frozen or its cooldown hasn't elapsed, or when the cap is
+    /// below the minimum transfer amount. The fee assumes a receiver that isn't a fee recipient, so
+    /// a transfer to one can move a bit more. The checks on the receiver, its registration, the
+    /// blacklist and the whitelist, aren't accounted for since it isn't known here.
+    pub fn max_transferable(&self, account_id: AccountId) -> U128 {
+        let spendable = self.spendable_balance_of(account_id.clone()).0;
+        let mut max_amount = self.max_amount_within(&account_id, spendable);
+        if let (Some(daily_limit), Ok(Some((_, moved_today)))) =
+            (self.daily_limit, self.daily_record_after(&account_id, 0))
+        {
+            max_amount = std::cmp::min(max_amount, daily_limit.saturating_sub(moved_today));
+        }
+        if let Some(threshold_bps) = self.auto_pause_threshold_bps {
+            let threshold = basis_points_of(self.token.total_supply, threshold_bps);
+            max_amount = std::cmp::min(max_amount, threshold);
+        }
+        let allowed = self
+            .transfer_hook()
+            .check_transfer(&account_id, &account_id, max_amount)
+            .and_then(|_| self.check_cooldown(&account_id));
+        if max_amount == 0 || allowed.is_err() {
+            return 0.into();
+        }
+        max_amount.into()
+    }
+}
+
+impl Contract {
+    /// Returns the largest amount whose transfer from `sender_id` takes at most `balance` from its
+    /// balance, with the fee and the burned part charged on top of the amount.
+    fn max_amount_within(&self, sender_id: &AccountId, balance: Balance) -> Balance {
+        // The charge grows with the amount, so a binary search finds the largest one that fits.
+        let (mut low, mut high) = (0, balance);
+        while low < high {
+            let mid = high - (high - low) / 2;
+            match self.transfer_debit(sender_id, sender_id, mid) {
+                Ok(debit) if debit <= balance => low = mid,
+                _ => high = mid - 1,
+            }
+        }
+        low
+    }
+
+    /// Runs the checks of a transfer and returns its fee and burned amount.
+    pub(crate) fn check_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(Balance, Balance), String> {
+        self.transfer_hook()
+            .check_transfer(sender_id, receiver_id, amount)
+            .map_err(|error| error.to_string())?;
+        if let Some(threshold) = self.circuit_breaker_threshold(sender_id, amount) {
+            return Err(format!(
+                "The transfer of {} exceeds the auto-pause threshold of {}, the contract would be paused",
+                amount, threshold
+            ));
+        }
+        self.transfer_debit(sender_id, receiver_id, amount)
+            .and_then(|debit| self.check_spendable(sender_id, debit))
+            .and_then(|_| self.daily_record_after(sender_id, amount).map(|_| ()))
+            .and_then(|_| self.check_cooldown(sender_id))
+            .and_then(|_| ensure(sender_id != receiver_id, ContractError::SameAccount))
+            .and_then(|_| ensure(amount > 0, ContractError::ZeroAmount))
+            .and_then(|_| {
+                ensure(
+                    self.token.accounts.contains_key(receiver_id),
+                    ContractError::NotRegistered(receiver_id.clone()),
+                )
+            })
+            .map_err(|error| error.to_string())?;
+        Ok(self.transfer_deductions(sender_id, receiver_id, amount))
+    }
+}
@@ -0,0 +1,382 @@
+//! Balance snapshots for governance voting.
+//!
+//! Iterating all holders may not fit into a single call, so a snapshot is recorded in pages: the
+//! first `snapshot` call with `from_index` 0 starts a new snapshot, the following calls continue
+//! the latest one from where the previous page stopped, and `finalize_snapshot` makes it queryable
+//! once every holder is recorded. Until then balances are frozen: transfers, mints, burns and
+//! unregistrations fail with `ERR_SNAPSHOT_IN_PROGRESS`, so all pages see the balances of the block
+//! the snapshot was started at. A snapshot that isn't finalized within `SNAPSHOT_TIMEOUT_BLOCKS`
+//! blocks expires: balances are no longer frozen, it can't be continued or finalized, and the next
+//! `snapshot` call with `from_index` 0 starts over. A transfer paid in the fee token is dropped if
+//! its fee arrives during a snapshot. Refunds of `ft_transfer_call` can't be delayed, so they
+//! record the balances from before the refund for the accounts that aren't recorded yet.
+//!
+//! `export_balances` pages over the current balances without recording anything, for indexers that
+//! start late and need a state to build upon.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+/// The number of blocks after which a snapshot that isn't finalized expires.
+pub const SNAPSHOT_TIMEOUT_BLOCKS: u64 = 10_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Snapshot {
+    pub block_height: u64,
+    pub total_supply: U128,
+    /// The index of the holder the next page starts at.
+    pub next_index: u64,
+    pub finalized: bool,
+}
+
+impl Snapshot {
+    /// Returns whether the snapshot wasn't finalized within `SNAPSHOT_TIMEOUT_BLOCKS` blocks.
+    pub fn is_expired(&self) -> bool {
+        !self.finalized
+            && env::block_height() >= self.block_height.saturating_add(SNAPSHOT_TIMEOUT_BLOCKS)
+    }
+}
+
+/// A page of the current balances, with the total supply and the block height they were read at.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BalanceExport {
+    pub balances: Vec<(AccountId, U128)>,
+    pub total_supply: U128,
+    pub block_height: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Records the balances of up to `limit` holders starting at `from_index` and returns the
+    /// snapshot id. `from_index` 0 starts a new snapshot at the current block height, otherwise the
+    /// latest snapshot is continued and `from_index` has to be where its previous page stopped. A
+    /// new snapshot can start once the latest one is finalized or has expired. The latest snapshot
+    /// can't be continued once it has expired. Can only be called by the owner. The attached
+    /// deposit has to cover the storage of the recorded balances, the rest is refunded.
+    #[payable]
+    pub fn snapshot(&mut self, from_index: u64, limit: u64) -> u64 {
+        self.assert_owner();
+        self.assert_holders_indexed();
+        let initial_storage_usage = env::storage_usage();
+        if from_index == 0 {
+            assert!(!self.is_snapshot_in_progress(), "Another snapshot is in progress");
+            self.snapshots.push(&Snapshot {
+                block_height: env::block_height(),
+                total_supply: self.token.total_supply.into(),
+                next_index: 0,
+                finalized: false,
+            });
+        }
+        let snapshot_id = self.snapshots.len().checked_sub(1).expect("No snapshot is started");
+        let mut snapshot = self.snapshots.get(snapshot_id).unwrap();
+        assert!(!snapshot.finalized, "The snapshot is already finalized");
+        assert!(!snapshot.is_expired(), "The snapshot has expired");
+        assert!(
+            from_index == snapshot.next_index,
+            "The snapshot continues at index {}",
+            snapshot.next_index
+        );
+        let holders = self.holders.as_vector();
+        let end = std::cmp::min(from_index.saturating_add(limit), holders.len());
+        for index in from_index..end {
+            let account_id = holders.get(index).unwrap();
+            let key = (snapshot_id, account_id);
+            // A refund may have recorded the balance before this page.
+            if self.snapshot_balances.contains_key(&key) {
+                continue;
+            }
+            let balance = self.token.accounts.get(&key.1).unwrap_or(0);
+            if balance > 0 {
+                self.snapshot_balances.insert(&key, &balance);
+            }
+        }
+        snapshot.next_index = end;
+        self.snapshots.replace(snapshot_id, &snapshot);
+        settle_storage_deposit(initial_storage_usage);
+        snapshot_id
+    }
+
+    /// Marks the snapshot as complete, so its balances can be queried and transfers resume.
+    /// Can only be called by the owner once every holder is recorded, before the snapshot expires.
+    pub fn finalize_snapshot(&mut self, snapshot_id: u64) {
+        self.assert_owner();
+        let mut snapshot = self.snapshots.get(snapshot_id).expect("Snapshot not found");
+        assert!(!snapshot.is_expired(), "The snapshot has expired");
+        assert!(
+            snapshot.next_index >= self.holders.len(),
+            "The snapshot hasn't recorded every holder yet"
+        );
+        snapshot.finalized = true;
+        self.snapshots.replace(snapshot_id, &snapshot);
+    }
+
+    /// Returns whether the latest snapshot is still being recorded, which freezes all balances.
+    pub fn is_snapshot_in_progress(&self) -> bool {
+        match self.snapshots.len().checked_sub(1) {
+            Some(snapshot_id) => {
+                let snapshot = self.snapshots.get(snapshot_id).unwrap();
+                !snapshot.finalized && !snapshot.is_expired()
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the snapshot with the given id.
+    pub fn get_snapshot(&self, snapshot_id: u64) -> Option<Snapshot> {
+        self.snapshots.get(snapshot_id)
+    }
+
+    /// Returns the balance of `account_id` recorded in a finalized snapshot.
+    pub fn balance_at_snapshot(&self, snapshot_id: u64, account_id: AccountId) -> U128 {
+        let snapshot = self.snapshots.get(snapshot_id).expect("Snapshot not found");
+        assert!(snapshot.finalized, "The snapshot is not finalized");
+        self.snapshot_balances.get(&(snapshot_id, account_id)).unwrap_or(0).into()
+    }
+
+    /// Returns the current balances of up to `limit` holders starting at `from_index`, in the
+    /// order of `list_accounts`. Pages read at different block heights may not add up to the
+    /// same total supply.
+    pub fn export_balances(&self, from_index: u64, limit: u64) -> BalanceExport {
+        let balances = self
+            .list_accounts(from_index, limit)
+            .into_iter()
+            .map(|account_id| {
+                let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+                (account_id, balance.into())
+            })
+            .collect();
+        BalanceExport {
+            balances,
+            total_supply: self.token.total_supply.into(),
+            block_height: env::block_height().into(),
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_no_snapshot_in_progress(&self) {
+        require(!self.is_snapshot_in_progress(), ContractError::SnapshotInProgress);
+    }
+
+    /// Records the current balance of `account_id` in the snapshot being recorded, unless a page
+    /// already did. Called before a balance changes while the snapshot is in progress.
+    pub(crate) fn internal_checkpoint_balance(&mut self, account_id: &AccountId) {
+        if !self.is_snapshot_in_progress() {
+            return;
+        }
+        let key = (self.snapshots.len() - 1, account_id.clone());
+        if !self.snapshot_balances.contains_key(&key) {
+            let balance = self.token.accounts.get(account_id).unwrap_or(0);
+            self.snapshot_balances.insert(&key, &balance);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    use super::*;
+
+    const TOTAL_SUPPLY: Balance = 1_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into(), None, None);
+        for account_id in [accounts(1), accounts(3)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .predecessor_account_id(account_id.clone())
+                .build());
+            contract.storage_deposit(None, None);
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(2))
+                .build());
+            contract.ft_transfer(account_id, 1_000.into(), None);
+        }
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .block_index(42)
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_two_page_snapshot() {
+        let (mut context, mut contract) = setup();
+        let snapshot_id = contract.snapshot(0, 2);
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        assert_eq!(contract.snapshot(2, 2), snapshot_id);
+        contract.finalize_snapshot(snapshot_id);
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 500.into(), None);
+
+        let snapshot = contract.get_snapshot(snapshot_id).unwrap();
+        assert_eq!(snapshot.block_height, 42);
+        assert!(snapshot.finalized);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(1)).0, 1_000);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(3)).0, 1_000);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(2)).0, TOTAL_SUPPLY - 2_000);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(4)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_500);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SNAPSHOT_IN_PROGRESS")]
+    fn test_transfer_between_pages() {
+        let (mut context, mut contract) = setup();
+        contract.snapshot(0, 1);
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(3), 500.into(), None);
+    }
+
+    #[test]
+    fn test_refund_between_pages() {
+        let (mut context, mut contract) = setup();
+        let snapshot_id = contract.snapshot(0, 1);
+        assert!(contract.is_snapshot_in_progress());
+
+        // A transfer call from accounts(2) to accounts(3) that started before the snapshot.
+        testing_env!(
+            context
+                .storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(0))
+                .attached_deposit(0)
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"300\"".to_vec())]
+        );
+        contract.ft_resolve_transfer(accounts(2), accounts(3), 1_000.into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        contract.snapshot(1, 2);
+        contract.finalize_snapshot(snapshot_id);
+        assert!(!contract.is_snapshot_in_progress());
+
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(2)).0, TOTAL_SUPPLY - 2_000);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(3)).0, 1_000);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 700);
+        assert_eq!(contract.get_snapshot(snapshot_id).unwrap().total_supply.0, TOTAL_SUPPLY);
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.ft_transfer(accounts(3), 500.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 1_200);
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot hasn't recorded every holder yet")]
+    fn test_finalize_incomplete_snapshot() {
+        let (_, mut contract) = setup();
+        let snapshot_id = contract.snapshot(0, 2);
+        contract.finalize_snapshot(snapshot_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot continues at index 2")]
+    fn test_skip_snapshot_page() {
+        let (_, mut contract) = setup();
+        contract.snapshot(0, 2);
+        contract.snapshot(3, 2);
+    }
+
+    #[test]
+    fn test_export_balances() {
+        let (_, contract) = setup();
+        let first_page = contract.export_balances(0, 2);
+        assert_eq!(
+            first_page,
+            BalanceExport {
+                balances: vec![
+                    (accounts(2), (TOTAL_SUPPLY - 2_000).into()),
+                    (accounts(1), 1_000.into())
+                ],
+                total_supply: TOTAL_SUPPLY.into(),
+                block_height: 42.into(),
+            }
+        );
+        let second_page = contract.export_balances(2, 2);
+        assert_eq!(second_page.balances, vec![(accounts(3), 1_000.into())]);
+        assert_eq!(second_page.block_height, first_page.block_height);
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot is not finalized")]
+    fn test_query_unfinalized_snapshot() {
+        let (_, mut contract) = setup();
+        let snapshot_id = contract.snapshot(0, 10);
+        contract.balance_at_snapshot(snapshot_id, accounts(1));
+    }
+
+    #[test]
+    fn test_snapshot_expires() {
+        let (mut context, mut contract) = setup();
+        let expired_id = contract.snapshot(0, 2);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_index(42 + SNAPSHOT_TIMEOUT_BLOCKS)
+            .attached_deposit(1)
+            .build());
+        assert!(contract.get_snapshot(expired_id).unwrap().is_expired());
+        assert!(!contract.is_snapshot_in_progress());
+        contract.ft_transfer(accounts(3), 500.into(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 1_500);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(env::storage_byte_cost() * 1_000)
+            .build());
+        let snapshot_id = contract.snapshot(0, 10);
+        assert_eq!(snapshot_id, expired_id + 1);
+        contract.finalize_snapshot(snapshot_id);
+        assert_eq!(contract.balance_at_snapshot(snapshot_id, accounts(3)).0, 1_500);
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot has expired")]
+    fn test_continue_expired_snapshot() {
+        let (mut context, mut contract) = setup();
+        contract.snapshot(0, 2);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_index(42 + SNAPSHOT_TIMEOUT_BLOCKS)
+            .build());
+        contract.snapshot(2, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot has expired")]
+    fn test_finalize_expired_snapshot() {
+        let (mut context, mut contract) = setup();
+        let snapshot_id = contract.snapshot(0, 10);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_index(42 + SNAPSHOT_TIMEOUT_BLOCKS)
+            .build());
+        contract.finalize_snapshot(snapshot_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot is already finalized")]
+    fn test_continue_finalized_snapshot() {
+        let (_, mut contract) = setup();
+        let snapshot_id = contract.snapshot(0, 10);
+        contract.finalize_snapshot(snapshot_id);
+        contract.snapshot(3, 2);
+    }
+}
@@ -0,0 +1,76 @@
+//! Recovery of other fungible tokens sent to this contract's account by mistake.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Sends `amount` of the fungible token `token_id` held by this contract's account to `to`.
+    /// Can only be called by the owner. Refuses to sweep this contract's own token, whose balances
+    /// are tracked by the contract itself. Requires exactly 1 yoctoNEAR attached, which is
+    /// forwarded to the `ft_transfer` call.
+    #[payable]
+    pub fn sweep_foreign_token(
+        &mut self,
+        token_id: AccountId,
+        to: AccountId,
+        amount: U128,
+    ) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(token_id != env::current_account_id(), "Can't sweep the contract's own token");
+        log!("Sweeping {} of @{} to @{}", amount.0, token_id, to);
+        let args = near_sdk::serde_json::json!({
+            "receiver_id": to,
+            "amount": amount,
+            "memo": "sweep",
+        });
+        Promise::new(token_id).function_call(
+            "ft_transfer".to_string(),
+            args.to_string().into_bytes(),
+            1,
+            GAS_FOR_SWEEP,
+        )
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), 1_000_000.into(), None, None);
+        testing_env!(context.attached_deposit(1).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_sweep_foreign_token() {
+        let (_, mut contract) = setup();
+        contract.sweep_foreign_token(accounts(4), accounts(1), 500.into());
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(4));
+        assert!(format!("{:?}", receipts[0].actions).contains("ft_transfer"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't sweep the contract's own token")]
+    fn test_sweep_own_token() {
+        let (_, mut contract) = setup();
+        contract.sweep_foreign_token(accounts(0), accounts(1), 500.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_sweep_not_owner() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.sweep_foreign_token(accounts(4), accounts(1), 500.into());
+    }
+}
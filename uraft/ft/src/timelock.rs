@@ -0,0 +1,207 @@
+//! Timelocked admin actions. Critical changes are queued by the owner first and can only be
+//! executed after a delay, so holders can see them coming. The setters of the fee and the owner,
+//! `set_fee_basis_points` and `set_owner`, queue their action with the minimum delay instead of
+//! applying it.
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// The shortest delay between queueing and executing an admin action (1 day).
+pub(crate) const MIN_ACTION_DELAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AdminAction {
+    SetFee { fee_basis_points: u16 },
+    SetOwner { new_owner: AccountId },
+    Pause,
+    Unpause,
+    SetPauseFlags { flags: PauseFlags },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueuedAction {
+    pub action: AdminAction,
+    pub execute_after_ns: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Queues `action` to be executed at or after `execute_after_ns`, which has to be at least a
+    /// day from now. Returns the index of the queued action. Can only be called by the owner.
+    pub fn queue_action(&mut self, action: AdminAction, execute_after_ns: U64) -> u64 {
+        self.assert_owner();
+        self.internal_queue_action(action, execute_after_ns)
+    }
+
+    /// Executes the queued action at `index` once its delay has passed.
+    /// Can only be called by the owner.
+    pub fn execute_action(&mut self, index: u64) {
+        self.assert_owner();
+        let queued = self.internal_take_action(index);
+        assert!(
+            env::block_timestamp() >= queued.execute_after_ns.0,
+            "The action can't be executed before {}",
+            queued.execute_after_ns.0
+        );
+        log!("Executing admin action #{}", index);
+        match queued.action {
+            AdminAction::SetFee { fee_basis_points } => {
+                self.internal_set_fee_basis_points(fee_basis_points)
+            }
+            AdminAction::SetOwner { new_owner } => self.internal_set_owner(new_owner),
+            AdminAction::Pause => self.internal_pause(),
+            AdminAction::Unpause => self.internal_unpause(),
+            AdminAction::SetPauseFlags { flags } => self.internal_set_pause_flags(flags),
+        }
+    }
+
+    /// Cancels the queued action at `index`. Can only be called by the owner.
+    pub fn cancel_action(&mut self, index: u64) {
+        self.assert_owner();
+        self.internal_take_action(index);
+        log!("Cancelled admin action #{}", index);
+    }
+
+    /// Returns the action queued at `index`, or `None` if it was executed or cancelled.
+    pub fn get_queued_action(&self, index: u64) -> Option<QueuedAction> {
+        self.admin_actions.get(index).flatten()
+    }
+}
+
+impl Contract {
+    fn internal_queue_action(&mut self, action: AdminAction, execute_after_ns: U64) -> u64 {
+        assert!(
+            execute_after_ns.0 >= env::block_timestamp().saturating_add(MIN_ACTION_DELAY_NS),
+            "The action can't be executed earlier than {} ns from now",
+            MIN_ACTION_DELAY_NS
+        );
+        if let AdminAction::SetFee { fee_basis_points } = action {
+            assert_valid_fee(fee_basis_points);
+        }
+        let index = self.admin_actions.len();
+        log!("Queued admin action #{}: {:?}", index, action);
+        self.admin_actions.push(&Some(QueuedAction { action, execute_after_ns }));
+        index
+    }
+
+    /// Queues `action` to be executed as soon as the minimum delay allows.
+    pub(crate) fn internal_queue_action_with_min_delay(&mut self, action: AdminAction) -> u64 {
+        let execute_after_ns = env::block_timestamp().saturating_add(MIN_ACTION_DELAY_NS);
+        self.internal_queue_action(action, execute_after_ns.into())
+    }
+
+    fn internal_take_action(&mut self, index: u64) -> QueuedAction {
+        let queued = self
+            .admin_actions
+            .get(index)
+            .flatten()
+            .unwrap_or_else(|| env::panic_str("The action is not queued"));
+        self.admin_actions.replace(index, &None);
+        queued
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const NOW: u64 = 1_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(NOW);
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), 1_000_000.into(), None, None);
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_execute_after_delay() {
+        let (mut context, mut contract) = setup();
+        let execute_after_ns = NOW + MIN_ACTION_DELAY_NS;
+        let fee = contract
+            .queue_action(AdminAction::SetFee { fee_basis_points: 50 }, execute_after_ns.into());
+        let pause = contract.queue_action(AdminAction::Pause, execute_after_ns.into());
+        let unpause = contract.queue_action(AdminAction::Unpause, execute_after_ns.into());
+        let owner = contract.queue_action(
+            AdminAction::SetOwner { new_owner: accounts(3) },
+            execute_after_ns.into(),
+        );
+        assert_eq!(
+            contract.get_queued_action(pause),
+            Some(QueuedAction {
+                action: AdminAction::Pause,
+                execute_after_ns: execute_after_ns.into()
+            })
+        );
+
+        testing_env!(context.block_timestamp(execute_after_ns).build());
+        contract.execute_action(fee);
+        contract.execute_action(pause);
+        assert!(contract.is_paused());
+        contract.execute_action(unpause);
+        assert!(!contract.is_paused());
+        contract.execute_action(owner);
+        assert_eq!(contract.get_fee_basis_points(), 50);
+        assert_eq!(contract.get_owner(), accounts(3));
+        assert_eq!(contract.get_queued_action(owner), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The action can't be executed before")]
+    fn test_execute_early() {
+        let (mut context, mut contract) = setup();
+        let execute_after_ns = NOW + MIN_ACTION_DELAY_NS;
+        let index = contract.queue_action(AdminAction::Pause, execute_after_ns.into());
+        testing_env!(context.block_timestamp(execute_after_ns - 1).build());
+        contract.execute_action(index);
+    }
+
+    #[test]
+    #[should_panic(expected = "The action can't be executed earlier than")]
+    fn test_queue_without_delay() {
+        let (_, mut contract) = setup();
+        contract.queue_action(AdminAction::Pause, (NOW + MIN_ACTION_DELAY_NS - 1).into());
+    }
+
+    #[test]
+    fn test_set_fee_queues_action() {
+        let (mut context, mut contract) = setup();
+        let index = contract.set_fee_basis_points(50);
+        assert_eq!(contract.get_fee_basis_points(), 0);
+        assert_eq!(
+            contract.get_queued_action(index),
+            Some(QueuedAction {
+                action: AdminAction::SetFee { fee_basis_points: 50 },
+                execute_after_ns: (NOW + MIN_ACTION_DELAY_NS).into()
+            })
+        );
+
+        testing_env!(context.block_timestamp(NOW + MIN_ACTION_DELAY_NS).build());
+        contract.execute_action(index);
+        assert_eq!(contract.get_fee_basis_points(), 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_set_owner_without_deposit() {
+        let (_, mut contract) = setup();
+        contract.set_owner(accounts(3));
+    }
+
+    #[test]
+    fn test_cancel_action() {
+        let (_, mut contract) = setup();
+        let index = contract.queue_action(AdminAction::Pause, (NOW + MIN_ACTION_DELAY_NS).into());
+        contract.cancel_action(index);
+        assert_eq!(contract.get_queued_action(index), None);
+    }
+}
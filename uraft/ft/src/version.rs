@@ -0,0 +1,70 @@
+//! Version and source metadata of the deployed contract, following NEP-330.
+use crate::*;
+use near_sdk::serde::Serialize;
+
+/// The version of this crate, compiled into the binary.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The repository of this crate, empty if it isn't set in `Cargo.toml`.
+const LINK: &str = env!("CARGO_PKG_REPOSITORY");
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Standard {
+    pub standard: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+    pub version: Option<String>,
+    pub link: Option<String>,
+    pub standards: Vec<Standard>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the version of the deployed contract code.
+    pub fn contract_version(&self) -> String {
+        VERSION.to_string()
+    }
+
+    /// Returns the version, source link and implemented standards of the deployed contract.
+    pub fn contract_source_metadata(&self) -> ContractSourceMetadata {
+        let standard = |standard: &str, version: &str| Standard {
+            standard: standard.to_string(),
+            version: version.to_string(),
+        };
+        ContractSourceMetadata {
+            version: Some(VERSION.to_string()),
+            link: Some(LINK.to_string()).filter(|link| !link.is_empty()),
+            standards: vec![
+                standard("nep141", "1.0.0"),
+                standard("nep145", "1.0.0"),
+                standard("nep148", "1.0.0"),
+                standard("nep330", "1.1.0"),
+            ],
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_contract_version() {
+        testing_env!(VMContextBuilder::new().current_account_id(accounts(0)).build());
+        let contract = Contract::new_default_meta(accounts(2), 1_000.into(), None, None);
+        let version = contract.contract_version();
+        assert!(!version.is_empty());
+        assert_eq!(version, env!("CARGO_PKG_VERSION"));
+
+        let metadata = contract.contract_source_metadata();
+        assert_eq!(metadata.version, Some(version));
+        assert!(metadata.standards.iter().any(|standard| standard.standard == "nep141"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Wrapping of native NEAR. A token initialized as `wrappable` is only minted by depositing NEAR
+//! and only burned by withdrawing it, so its total supply equals the wrapped NEAR held by the
+//! contract. Tokens burned otherwise, e.g. by a transfer burn or a forced `storage_unregister`,
+//! leave their NEAR in the contract, so the supply never exceeds the backing.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Mints tokens equal to the attached deposit to the predecessor. An unregistered predecessor
+    /// is registered first, and the minimum storage balance is taken from the deposit.
+    #[payable]
+    pub fn near_deposit(&mut self) {
+        self.assert_wrappable();
+        self.assert_mints_not_paused();
+        let account_id = env::predecessor_account_id();
+        let mut amount = env::attached_deposit();
+        if !self.token.accounts.contains_key(&account_id) {
+            let min_balance = self.storage_balance_bounds().min.0;
+            assert!(
+                amount > min_balance,
+                "The attached deposit has to exceed the minimum storage balance"
+            );
+            self.internal_register_account(&account_id);
+            amount -= min_balance;
+        }
+        assert!(amount > 0, "The deposit should be a positive number");
+        self.token.internal_deposit(&account_id, amount);
+        self.total_minted += amount;
+        self.emit_event(Nep141Event::FtMint(&[FtMint {
+            owner_id: &account_id,
+            amount: &amount.into(),
+            memo: Some("Wrap"),
+        }]));
+        self.internal_record_supply();
+        self.notify_supply_change();
+    }
+
+    /// Burns `amount` tokens of the predecessor and transfers the same amount of NEAR back.
+    /// Requires exactly 1 yoctoNEAR attached for security reasons.
+    #[payable]
+    pub fn near_withdraw(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        self.assert_wrappable();
+        self.assert_burns_not_paused();
+        let account_id = env::predecessor_account_id();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        self.assert_spendable(&account_id, amount.0);
+        self.token.internal_withdraw(&account_id, amount.0);
+        self.total_burned += amount.0;
+        self.emit_event(Nep141Event::FtBurn(&[FtBurn {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: Some("Unwrap"),
+        }]));
+        self.internal_record_supply();
+        self.notify_supply_change();
+        Promise::new(account_id).transfer(amount.0)
+    }
+}
+
+impl Contract {
+    fn assert_wrappable(&self) {
+        assert!(self.wrappable, "Wrapping NEAR isn't enabled for this token");
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const ONE_NEAR: Balance = 10u128.pow(24);
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(accounts(0)).predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        let metadata = FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Wrapped NEAR".to_string(),
+            symbol: "wNEAR".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        };
+        let contract = Contract::new(
+            accounts(2),
+            0.into(),
+            metadata,
+            Some(InitConfig { wrappable: true, ..Default::default() }),
+        );
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .predecessor_account_id(accounts(1))
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_near_deposit() {
+        let (mut context, mut contract) = setup();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        contract.near_deposit();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, ONE_NEAR - min_balance);
+
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.near_deposit();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 2 * ONE_NEAR - min_balance);
+        assert_eq!(contract.ft_total_supply().0, 2 * ONE_NEAR - min_balance);
+    }
+
+    #[test]
+    fn test_near_withdraw() {
+        let (mut context, mut contract) = setup();
+        contract.near_deposit();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.near_withdraw((ONE_NEAR / 2).into());
+        let balance = contract.ft_balance_of(accounts(1)).0;
+        assert_eq!(balance, ONE_NEAR / 2 - contract.storage_balance_bounds().min.0);
+        assert_eq!(contract.ft_total_supply().0, balance);
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert!(format!("{:?}", receipts[0].actions).contains("Transfer"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR_MINTING_DISABLED: Wrapped NEAR can only be minted and burned by wrapping"
+    )]
+    fn test_mint_wrapped_near() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ft_mint(accounts(2), ONE_NEAR.into(), None);
+    }
+}
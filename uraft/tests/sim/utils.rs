@@ -74,7 +74,8 @@ pub fn init_with_macros(
         // init method
         init_method: new_default_meta(
             root.account_id(),
-            initial_balance.into()
+            initial_balance.into(),
+            None
         )
     );
     let alice = root.create_user("alice".parse().unwrap(), to_yocto("100"));